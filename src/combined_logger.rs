@@ -0,0 +1,159 @@
+use std::io::Write;
+
+use chrono::Local;
+use serde_json::map::Map;
+use serde_json::Value;
+
+use crate::{BunyanLine, LoggerOutputConfig};
+
+/// Default assumed HTTP version
+const DEFAULT_HTTP_VERSION: &str = "1.1";
+/// Time format of the Apache access log (eg `10/Oct/2000:13:55:36 -0700`)
+const ACCESS_LOG_TIME_FORMAT: &str = "%d/%b/%Y:%H:%M:%S %z";
+
+/// Writes a log record containing both a `req` and a `res` (eg as written by restify's audit
+/// logger) as an Apache/NCSA combined access log line in the form of
+/// `host - - [time] "method url HTTP/version" status bytes "referer" "user-agent"`. Records
+/// that don't contain both are skipped. Values that weren't logged are written as `-`.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `log` - Log record to write
+/// * `output_config` - Configuration containing the time zone to display
+///
+pub fn write_combined_line<W: Write>(
+    writer: &mut W,
+    log: &BunyanLine,
+    output_config: &LoggerOutputConfig,
+) {
+    let (req, res) = match (
+        log.other.get("req").and_then(Value::as_object),
+        log.other.get("res").and_then(Value::as_object),
+    ) {
+        (Some(req), Some(res)) => (req, res),
+        _ => return,
+    };
+
+    let host = log
+        .other
+        .get("remoteAddress")
+        .or_else(|| req.get("remoteAddress"))
+        .map(|address| string_or_value!(address))
+        .filter(|address| !address.is_empty())
+        .unwrap_or_else(|| "-".to_string());
+
+    let time = if output_config.display_local_time {
+        log.time
+            .with_timezone(&Local)
+            .format(ACCESS_LOG_TIME_FORMAT)
+            .to_string()
+    } else {
+        log.time.format(ACCESS_LOG_TIME_FORMAT).to_string()
+    };
+
+    let request = format!(
+        "{} {} HTTP/{}",
+        text_field(req, "method").unwrap_or_else(|| "-".to_string()),
+        text_field(req, "url").unwrap_or_else(|| "-".to_string()),
+        text_field(req, "httpVersion").unwrap_or_else(|| DEFAULT_HTTP_VERSION.to_string())
+    );
+
+    let status = text_field(res, "statusCode").unwrap_or_else(|| "-".to_string());
+
+    // As with Apache's %b, a response without a body is written as -
+    let bytes = header(res, "content-length")
+        .filter(|length| length.parse::<u64>().is_ok_and(|length| length > 0))
+        .unwrap_or_else(|| "-".to_string());
+
+    let referer = header(req, "referer").unwrap_or_else(|| "-".to_string());
+    let user_agent = header(req, "user-agent").unwrap_or_else(|| "-".to_string());
+
+    wln!(
+        writer,
+        "{} - - [{}] \"{}\" {} {} \"{}\" \"{}\"",
+        escape(&host),
+        time,
+        escape(&request),
+        escape(&status),
+        bytes,
+        escape(&referer),
+        escape(&user_agent)
+    );
+}
+
+/// Returns the text of the field of the object if it is a string or number.
+fn text_field(object: &Map<String, Value>, key: &str) -> Option<String> {
+    match object.get(key)? {
+        Value::String(text) if !text.is_empty() => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+/// Returns the value of the named header of a request or response. Header names are matched
+/// regardless of case and headers may be logged as an object or as the raw header text.
+fn header(object: &Map<String, Value>, name: &str) -> Option<String> {
+    let headers = object.get("headers").or_else(|| object.get("header"))?;
+
+    match headers {
+        Value::Object(headers) => headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| string_or_value!(value)),
+        Value::String(headers) => headers.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            if key.trim().eq_ignore_ascii_case(name) {
+                Some(value.trim().to_string())
+            } else {
+                None
+            }
+        }),
+        _ => None,
+    }
+}
+
+/// Returns the text escaped as Apache escapes the fields of its access log, with quotes and
+/// backslashes preceded by a backslash and control characters written as `\xhh`.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_ascii_control() => escaped.push_str(&format!("\\x{:02x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_find_headers_regardless_of_case() {
+        let res: Map<String, Value> = serde_json::from_str(
+            r#"{"headers": {"Content-Length": 12}, "header": "HTTP/1.1 200 OK\r\nReferer: x\r\n"}"#,
+        )
+        .unwrap();
+        assert_eq!(header(&res, "content-length"), Some("12".to_string()));
+
+        let res: Map<String, Value> =
+            serde_json::from_str(r#"{"header": "HTTP/1.1 200 OK\r\nContent-Length: 7\r\n"}"#)
+                .unwrap();
+        assert_eq!(header(&res, "content-length"), Some("7".to_string()));
+        assert_eq!(header(&res, "user-agent"), None);
+    }
+
+    #[test]
+    fn can_escape_fields() {
+        assert_eq!(
+            escape("GET /a\"b\\c\n HTTP/1.1"),
+            "GET /a\\\"b\\\\c\\x0a HTTP/1.1"
+        );
+    }
+}
@@ -1,42 +1,194 @@
 use core::fmt;
+use std::collections::HashMap;
 
-use quick_js::Context;
+use quick_js::{Context, JsValue};
+use serde_json::map::Map;
+use serde_json::Value;
 
+use crate::errors::ScriptCompileError;
+
+/// Name of the global JS array holding the compiled condition functions
+const CONDITIONS_GLOBAL: &str = "__bunyan_conditions";
+/// Name of the global JS function that evaluates all conditions against a record
+const FILTER_FUNCTION: &str = "__bunyan_filter";
+/// JS definitions of the log level constants available to user supplied expressions
+pub const JS_LEVEL_CONSTANTS: &str = r#"
+var TRACE = 10;
+var DEBUG = 20;
+var INFO = 30;
+var WARN = 40;
+var ERROR = 50;
+var FATAL = 60;
+"#;
+/// JS prelude defining the function used to evaluate records
+const PRELUDE: &str = r#"
+var __bunyan_conditions = [];
+function __bunyan_filter(record) {
+    return __bunyan_conditions.every(function (condition) {
+        try {
+            return Boolean(condition.call(record));
+        } catch (e) {
+            return false;
+        }
+    });
+}
+"#;
+
+/// Filters log records using one or more JS conditions in the same manner as
+/// the `-c` option of node-bunyan. Each condition is compiled once into a
+/// function and records are passed to it as data with `this` bound to the
+/// record. A record is only matched when all conditions return a truthy value.
+///
 pub struct ConditionFilter {
     context: Context,
-    condition: String,
+    conditions: Vec<String>,
 }
 
 impl ConditionFilter {
-    pub fn new<S>(condition: S) -> Self
+    pub fn new<I, S>(conditions: I) -> Result<Self, ScriptCompileError>
     where
+        I: IntoIterator<Item = S>,
         S: Into<String>,
     {
-        Self {
-            context: Context::new().unwrap(),
-            condition: condition.into(),
+        let conditions: Vec<String> = conditions.into_iter().map(Into::into).collect();
+        let context = Self::compile(&conditions)?;
+
+        Ok(Self {
+            context,
+            conditions,
+        })
+    }
+
+    /// Creates a new JS context containing the prelude and a compiled function for
+    /// each of the passed conditions.
+    ///
+    /// # Errors
+    ///
+    /// If a condition is not syntactically valid JS, a `ScriptCompileError` is returned
+    /// referencing the offending condition.
+    ///
+    fn compile(conditions: &[String]) -> Result<Context, ScriptCompileError> {
+        let context = Context::new().map_err(|e| ScriptCompileError::new("", e.to_string()))?;
+
+        context
+            .eval(JS_LEVEL_CONSTANTS)
+            .and_then(|_| context.eval(PRELUDE))
+            .map_err(|e| ScriptCompileError::new("", e.to_string()))?;
+
+        for condition in conditions {
+            let code =
+                format!("{CONDITIONS_GLOBAL}.push(function () {{ return ({condition}\n); }});");
+            context
+                .eval(code.as_str())
+                .map_err(|e| ScriptCompileError::new(condition.as_str(), e.to_string()))?;
         }
+
+        Ok(context)
     }
-    pub fn filter(&self, line: &str) -> bool {
-        self.context
-            .eval_as::<bool>(
-                format!("(function (){{return ({})}}).call({line})", self.condition).as_str(),
-            )
-            .unwrap()
+
+    /// Returns true if the passed record satisfies all conditions. Conditions that throw
+    /// an exception when evaluated are treated as not matching.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - Map containing the JSON data of the log record
+    ///
+    pub fn filter(&self, record: &Map<String, Value>) -> bool {
+        let js_record: HashMap<String, JsValue> = record
+            .iter()
+            .map(|(k, v)| (k.clone(), to_js_value(v)))
+            .collect();
+
+        matches!(
+            self.context
+                .call_function(FILTER_FUNCTION, vec![JsValue::from(js_record)]),
+            Ok(JsValue::Bool(true))
+        )
+    }
+}
+
+/// Converts a serde JSON value into the equivalent JS value.
+///
+/// # Arguments
+///
+/// * `value` - JSON value to convert
+///
+fn to_js_value(value: &Value) -> JsValue {
+    match value {
+        Value::Null => JsValue::Null,
+        Value::Bool(boolean) => JsValue::Bool(*boolean),
+        Value::Number(number) => match number.as_i64().map(i32::try_from) {
+            Some(Ok(int)) => JsValue::Int(int),
+            _ => JsValue::Float(number.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(text) => JsValue::String(text.clone()),
+        Value::Array(array) => JsValue::Array(array.iter().map(to_js_value).collect()),
+        Value::Object(map) => JsValue::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), to_js_value(v)))
+                .collect(),
+        ),
     }
 }
 
 impl fmt::Debug for ConditionFilter {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "ConditionFilter [`{}`]", self.condition)
+        write!(f, "ConditionFilter [`{}`]", self.conditions.join("` && `"))
     }
 }
 
 impl Clone for ConditionFilter {
     fn clone(&self) -> Self {
         Self {
-            context: Context::new().unwrap(),
-            condition: self.condition.clone(),
+            context: Self::compile(&self.conditions)
+                .expect("conditions were already compiled successfully"),
+            conditions: self.conditions.clone(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(json: &str) -> Map<String, Value> {
+        serde_json::from_str(json).expect("invalid test JSON")
+    }
+
+    #[test]
+    fn can_filter_with_level_constants() {
+        let filter = ConditionFilter::new(vec!["this.level >= WARN"]).unwrap();
+        assert!(filter.filter(&record(r#"{"level": 50}"#)));
+        assert!(!filter.filter(&record(r#"{"level": 30}"#)));
+    }
+
+    #[test]
+    fn multiple_conditions_are_anded() {
+        let filter =
+            ConditionFilter::new(vec!["this.pid == 123", "this.msg.indexOf('boom') != -1"])
+                .unwrap();
+        assert!(filter.filter(&record(r#"{"pid": 123, "msg": "it went boom"}"#)));
+        assert!(!filter.filter(&record(r#"{"pid": 123, "msg": "all good"}"#)));
+        assert!(!filter.filter(&record(r#"{"pid": 456, "msg": "it went boom"}"#)));
+    }
+
+    #[test]
+    fn exceptions_do_not_match() {
+        let filter = ConditionFilter::new(vec!["this.req.url == '/'"]).unwrap();
+        assert!(!filter.filter(&record(r#"{"msg": "no req"}"#)));
+        assert!(filter.filter(&record(r#"{"req": {"url": "/"}}"#)));
+    }
+
+    #[test]
+    fn record_data_is_not_evaluated_as_code() {
+        let filter = ConditionFilter::new(vec!["typeof pwned === 'undefined'"]).unwrap();
+        let malicious = record(r#"{"msg": "\"}); globalThis.pwned = true; ({\""}"#);
+        assert!(filter.filter(&malicious));
+        assert!(filter.filter(&malicious));
+    }
+
+    #[test]
+    fn invalid_condition_is_an_error() {
+        assert!(ConditionFilter::new(vec!["this.pid =="]).is_err());
+    }
+}
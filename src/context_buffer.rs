@@ -0,0 +1,356 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::errors::DurationParseError;
+
+/// Settings controlling which records surrounding a matching record are displayed
+#[derive(Debug, Clone, Default)]
+pub struct ContextConfig {
+    /// Number of records to display before each matching record
+    pub before: usize,
+    /// Number of records to display after each matching record
+    pub after: usize,
+    /// Display all records within this amount of time of each matching record
+    pub time_window: Option<Duration>,
+}
+
+/// Records to be written as a result of adding a record to a `ContextBuffer`
+pub struct ContextOutput<T> {
+    /// True when a separator should be written before the records because records have
+    /// been skipped since the last record written
+    pub separator: bool,
+    pub items: Vec<T>,
+}
+
+/// Look-behind buffer that tracks the records surrounding matching records so that they
+/// can be displayed as context in the manner of the `-A`, `-B` and `-C` flags of `grep`.
+///
+pub struct ContextBuffer<T> {
+    config: ContextConfig,
+    before: VecDeque<(DateTime<Utc>, T)>,
+    after_remaining: usize,
+    last_match_time: Option<DateTime<Utc>>,
+    has_written: bool,
+    has_skipped: bool,
+}
+
+impl<T> ContextBuffer<T> {
+    pub fn new(config: ContextConfig) -> Self {
+        Self {
+            config,
+            before: VecDeque::new(),
+            after_remaining: 0,
+            last_match_time: None,
+            has_written: false,
+            has_skipped: false,
+        }
+    }
+
+    /// Adds a record that matched the filters and returns it along with any buffered
+    /// records that precede it within the context window.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Timestamp of the record
+    /// * `item` - Record to add
+    ///
+    pub fn push_match(&mut self, time: DateTime<Utc>, item: T) -> ContextOutput<T> {
+        let before_count = self.config.before;
+        let buffered = self.before.len();
+        let earliest = self
+            .config
+            .time_window
+            .map(|window| window_start(time, window));
+        let mut items: Vec<T> = Vec::with_capacity(buffered + 1);
+
+        for (index, (item_time, buffered_item)) in self.before.drain(..).enumerate() {
+            let in_count = index + before_count >= buffered;
+            let in_window = earliest.is_some_and(|earliest| item_time >= earliest);
+
+            if in_count || in_window {
+                items.push(buffered_item);
+            } else {
+                self.has_skipped = true;
+            }
+        }
+
+        items.push(item);
+
+        let separator = self.has_written && self.has_skipped;
+        self.has_written = true;
+        self.has_skipped = false;
+        self.after_remaining = self.config.after;
+        self.last_match_time = Some(time);
+
+        ContextOutput { separator, items }
+    }
+
+    /// Adds a record that did not match the filters. The record is returned if it falls
+    /// within the context following a previous match. Otherwise, it is buffered in case
+    /// a subsequent record matches.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Timestamp of the record
+    /// * `item` - Record to add
+    ///
+    pub fn push_non_match(&mut self, time: DateTime<Utc>, item: T) -> ContextOutput<T> {
+        let in_after_window = match (self.last_match_time, self.config.time_window) {
+            (Some(match_time), Some(window)) => time <= window_end(match_time, window),
+            _ => false,
+        };
+
+        if self.after_remaining > 0 || in_after_window {
+            self.after_remaining = self.after_remaining.saturating_sub(1);
+
+            return ContextOutput {
+                separator: false,
+                items: vec![item],
+            };
+        }
+
+        self.before.push_back((time, item));
+
+        let earliest = self
+            .config
+            .time_window
+            .map(|window| window_start(time, window));
+
+        while self.before.len() > self.config.before {
+            let front_in_window = match (self.before.front(), earliest) {
+                (Some((front_time, _)), Some(earliest)) => *front_time >= earliest,
+                _ => false,
+            };
+
+            if front_in_window {
+                break;
+            }
+
+            self.before.pop_front();
+            self.has_skipped = true;
+        }
+
+        ContextOutput {
+            separator: false,
+            items: Vec::new(),
+        }
+    }
+
+    /// Returns true if records following the last match may still be displayed as its
+    /// context.
+    pub fn has_trailing_context(&self) -> bool {
+        self.after_remaining > 0
+            || (self.last_match_time.is_some() && self.config.time_window.is_some())
+    }
+
+    /// Adds a record once no further matches are wanted (eg because `--head` has been
+    /// satisfied). The record is returned if it falls within the context following the last
+    /// match. Otherwise, the context has ended and no further records are returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Timestamp of the record
+    /// * `item` - Record to add
+    ///
+    pub fn push_trailing(&mut self, time: DateTime<Utc>, item: T) -> Option<T> {
+        self.before.clear();
+
+        let in_after_window = match (self.last_match_time, self.config.time_window) {
+            (Some(match_time), Some(window)) => time <= window_end(match_time, window),
+            _ => false,
+        };
+
+        if self.after_remaining > 0 || in_after_window {
+            self.after_remaining = self.after_remaining.saturating_sub(1);
+            Some(item)
+        } else {
+            self.after_remaining = 0;
+            self.last_match_time = None;
+            None
+        }
+    }
+}
+
+/// Returns the earliest time within `window` before `time`. When the window reaches back
+/// further than can be represented, the earliest representable time is returned instead.
+pub(crate) fn window_start(time: DateTime<Utc>, window: Duration) -> DateTime<Utc> {
+    time.checked_sub_signed(window)
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+/// Returns the latest time within `window` after `time`. When the window reaches further
+/// than can be represented, the latest representable time is returned instead.
+pub(crate) fn window_end(time: DateTime<Utc>, window: Duration) -> DateTime<Utc> {
+    time.checked_add_signed(window)
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// Parses a duration such as `500ms`, `5s`, `2m`, `1h` or `1d`. A number without a unit
+/// is interpreted as seconds.
+///
+/// # Errors
+///
+/// If the input is not a positive number optionally followed by a known unit, or the
+/// duration is too long to be represented, a `DurationParseError` is returned.
+///
+pub fn parse_duration<S: Into<String>>(input: S) -> Result<Duration, DurationParseError> {
+    let input = input.into();
+    let trimmed = input.trim();
+    let unit_pos = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(unit_pos);
+
+    let value = match number.parse::<f64>() {
+        Ok(value) if value.is_finite() && value >= 0.0 => value,
+        _ => return Err(DurationParseError::from(input)),
+    };
+
+    let millis_per_unit = match unit.trim() {
+        "ms" => 1.0,
+        "" | "s" | "sec" | "secs" => 1_000.0,
+        "m" | "min" | "mins" => 60_000.0,
+        "h" | "hr" | "hrs" => 3_600_000.0,
+        "d" | "day" | "days" => 86_400_000.0,
+        _ => return Err(DurationParseError::from(input)),
+    };
+
+    let millis = (value * millis_per_unit).round();
+
+    if millis >= i64::MAX as f64 {
+        return Err(DurationParseError::from(input));
+    }
+
+    Duration::try_milliseconds(millis as i64).ok_or_else(|| DurationParseError::from(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(seconds, 0).unwrap()
+    }
+
+    /// Runs the passed records through a buffer returning the written output with `--`
+    /// representing separators.
+    fn run(config: ContextConfig, records: Vec<(i64, bool)>) -> Vec<String> {
+        let mut buffer = ContextBuffer::new(config);
+        let mut written = Vec::new();
+
+        for (index, (seconds, is_match)) in records.into_iter().enumerate() {
+            let output = if is_match {
+                buffer.push_match(at(seconds), index)
+            } else {
+                buffer.push_non_match(at(seconds), index)
+            };
+
+            if output.separator {
+                written.push("--".to_string());
+            }
+            written.extend(output.items.iter().map(|item| item.to_string()));
+        }
+
+        written
+    }
+
+    #[test]
+    fn can_display_records_before_and_after() {
+        let config = ContextConfig {
+            before: 1,
+            after: 1,
+            time_window: None,
+        };
+        let records = vec![
+            (0, false),
+            (1, false),
+            (2, true),
+            (3, false),
+            (4, false),
+            (5, false),
+            (6, true),
+        ];
+
+        assert_eq!(run(config, records), vec!["1", "2", "3", "--", "5", "6"]);
+    }
+
+    #[test]
+    fn no_separator_between_adjacent_groups() {
+        let config = ContextConfig {
+            before: 1,
+            after: 1,
+            time_window: None,
+        };
+        let records = vec![(0, true), (1, false), (2, false), (3, true)];
+
+        assert_eq!(run(config, records), vec!["0", "1", "2", "3"]);
+    }
+
+    #[test]
+    fn can_display_records_within_time_window() {
+        let config = ContextConfig {
+            before: 0,
+            after: 0,
+            time_window: Some(Duration::seconds(5)),
+        };
+        let records = vec![
+            (0, false),
+            (10, false),
+            (12, false),
+            (15, true),
+            (19, false),
+            (21, false),
+        ];
+
+        assert_eq!(run(config, records), vec!["1", "2", "3", "4"]);
+    }
+
+    #[test]
+    fn trailing_context_ends_after_last_match() {
+        let config = ContextConfig {
+            before: 0,
+            after: 1,
+            time_window: Some(Duration::seconds(5)),
+        };
+        let mut buffer = ContextBuffer::new(config);
+        assert!(!buffer.has_trailing_context());
+
+        buffer.push_match(at(0), 0);
+        assert!(buffer.has_trailing_context());
+        assert_eq!(buffer.push_trailing(at(10), 1), Some(1));
+        assert_eq!(buffer.push_trailing(at(4), 2), Some(2));
+        assert_eq!(buffer.push_trailing(at(6), 3), None);
+        assert!(!buffer.has_trailing_context());
+    }
+
+    #[test]
+    fn can_parse_durations() {
+        assert_eq!(
+            parse_duration("500ms").unwrap(),
+            Duration::milliseconds(500)
+        );
+        assert_eq!(parse_duration("5s").unwrap(), Duration::seconds(5));
+        assert_eq!(parse_duration("5").unwrap(), Duration::seconds(5));
+        assert_eq!(parse_duration("1.5m").unwrap(), Duration::seconds(90));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::hours(2));
+        assert!(parse_duration("5 parsecs").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("999999999999999999d").is_err());
+    }
+
+    #[test]
+    fn time_window_beyond_representable_times_has_no_bounds() {
+        let window = parse_duration("999999999d").unwrap();
+        let config = ContextConfig {
+            before: 0,
+            after: 0,
+            time_window: Some(window),
+        };
+        let records = vec![(0, false), (10, true), (20, false)];
+
+        assert_eq!(run(config, records), vec!["0", "1", "2"]);
+        assert_eq!(window_start(at(0), window), DateTime::<Utc>::MIN_UTC);
+        assert_eq!(window_end(at(0), window), DateTime::<Utc>::MAX_UTC);
+    }
+}
@@ -36,7 +36,7 @@ where
 }
 
 #[derive(Debug, Clone)]
-struct TimeStampParseError {
+pub(crate) struct TimeStampParseError {
     pub timestamp_input: String,
     pub rfc3339_parse_error: Option<String>,
     pub rfc2822_parse_error: Option<String>,
@@ -77,7 +77,7 @@ impl StdError for TimeStampParseError {
     }
 }
 
-fn parse_timestamp(time: &str) -> Result<DateTime<Utc>, TimeStampParseError> {
+pub(crate) fn parse_timestamp(time: &str) -> Result<DateTime<Utc>, TimeStampParseError> {
     let mut parse_error = TimeStampParseError {
         timestamp_input: time.to_string(),
         rfc3339_parse_error: None,
@@ -115,14 +115,10 @@ mod tests {
     ) {
         match parse_result {
             Ok(parsed) => {
-                assert_eq!(parsed.timestamp_nanos(), expected_as_epoch);
+                assert_eq!(parsed.timestamp_nanos_opt().unwrap(), expected_as_epoch);
             }
             Err(error) => {
-                eprintln!(
-                    "Error parsing [{}]: {}",
-                    error.timestamp_input,
-                    error.to_string()
-                );
+                eprintln!("Error parsing [{}]: {}", error.timestamp_input, error);
                 panic!("{}", error)
             }
         }
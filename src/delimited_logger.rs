@@ -0,0 +1,153 @@
+use std::io::Write;
+
+use chrono::{Local, SecondsFormat};
+
+use crate::field_filter::lookup_path;
+use crate::{BunyanLine, LogLevel, LoggerOutputConfig};
+
+/// Columns written by `-o csv` and `-o tsv` when none are specified
+pub const DEFAULT_COLUMNS: [&str; 4] = ["time", "level", "name", "msg"];
+
+/// Separator of the values within a row of delimited output
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+pub enum Delimiter {
+    /// Comma separated values quoted as described by RFC 4180
+    Comma,
+    /// Tab separated values with tabs, line breaks and backslashes escaped
+    Tab,
+}
+
+impl Delimiter {
+    fn separator(self) -> &'static str {
+        match self {
+            Delimiter::Comma => ",",
+            Delimiter::Tab => "\t",
+        }
+    }
+
+    /// RFC 4180 requires rows to be terminated by CRLF
+    fn line_ending(self) -> &'static str {
+        match self {
+            Delimiter::Comma => "\r\n",
+            Delimiter::Tab => "\n",
+        }
+    }
+
+    /// Returns the value quoted or escaped so that it can be written within a row.
+    fn escape(self, value: &str) -> String {
+        match self {
+            Delimiter::Comma => {
+                if value.contains([',', '"', '\r', '\n']) {
+                    format!("\"{}\"", value.replace('"', "\"\""))
+                } else {
+                    value.to_string()
+                }
+            }
+            Delimiter::Tab => value
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r"),
+        }
+    }
+}
+
+/// Writes the header row naming the columns.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `columns` - Dotted paths of the fields written in each row
+/// * `delimiter` - Separator of the values within a row
+///
+pub fn write_delimited_header<W: Write>(writer: &mut W, columns: &[String], delimiter: Delimiter) {
+    write_row(writer, columns.iter().map(String::as_str), delimiter);
+}
+
+/// Writes a log record as a row of values. Nested values are written as JSON and missing
+/// values are left empty.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `log` - Log record to write
+/// * `columns` - Dotted paths of the fields written in each row
+/// * `delimiter` - Separator of the values within a row
+/// * `output_config` - Configuration containing the time zone to display
+///
+pub fn write_delimited_line<W: Write>(
+    writer: &mut W,
+    log: &BunyanLine,
+    columns: &[String],
+    delimiter: Delimiter,
+    output_config: &LoggerOutputConfig,
+) {
+    let values: Vec<String> = columns
+        .iter()
+        .map(|column| column_value(log, column, output_config).unwrap_or_default())
+        .collect();
+
+    write_row(writer, values.iter().map(String::as_str), delimiter);
+}
+
+fn write_row<'a, W, I>(writer: &mut W, values: I, delimiter: Delimiter)
+where
+    W: Write,
+    I: Iterator<Item = &'a str>,
+{
+    let row: Vec<String> = values.map(|value| delimiter.escape(value)).collect();
+    w!(
+        writer,
+        "{}{}",
+        row.join(delimiter.separator()),
+        delimiter.line_ending()
+    );
+}
+
+/// Returns the text of the field at the dotted path, or `None` if it is missing.
+fn column_value(
+    log: &BunyanLine,
+    column: &str,
+    output_config: &LoggerOutputConfig,
+) -> Option<String> {
+    match column {
+        "time" => Some(if output_config.display_local_time {
+            log.time
+                .with_timezone(&Local)
+                .to_rfc3339_opts(SecondsFormat::Millis, true)
+        } else {
+            log.time.to_rfc3339_opts(SecondsFormat::Millis, true)
+        }),
+        "level" => Some(LogLevel::from(log.level).as_string().into_owned()),
+        "name" => Some(log.name.clone()),
+        "hostname" => Some(log.hostname.clone()),
+        "pid" => Some(log.pid.to_string()),
+        "msg" => Some(log.msg.clone()),
+        "component" => log.component.clone(),
+        "v" => log.v.map(|v| v.to_string()),
+        path => lookup_path(&log.other, path)
+            .filter(|value| !value.is_null())
+            .map(|value| string_or_value!(value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_quote_csv_values() {
+        assert_eq!(Delimiter::Comma.escape("plain text"), "plain text");
+        assert_eq!(Delimiter::Comma.escape("a,b"), "\"a,b\"");
+        assert_eq!(
+            Delimiter::Comma.escape("say \"hi\"\nthere"),
+            "\"say \"\"hi\"\"\nthere\""
+        );
+    }
+
+    #[test]
+    fn can_escape_tsv_values() {
+        assert_eq!(Delimiter::Tab.escape("a\tb\nc\\d"), "a\\tb\\nc\\\\d");
+        assert_eq!(Delimiter::Tab.escape("a,\"b\""), "a,\"b\"");
+    }
+}
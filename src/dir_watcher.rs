@@ -0,0 +1,366 @@
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::io::{Read, Result as IoResult, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use colored::*;
+use regex::Regex;
+
+use crate::html_logger::write_html_text;
+use crate::{LogFormat, LoggerOutputConfig, RecordPipeline};
+
+/// Settings for watching a directory for log files to follow
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    pub dir: PathBuf,
+    /// Files within the directory whose names match the pattern are followed
+    pub pattern: Regex,
+}
+
+impl WatchConfig {
+    /// Creates a new configuration from a file name pattern in which `*` matches any
+    /// sequence of characters and `?` matches a single character (eg `job-*.log`).
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Directory to watch
+    /// * `pattern` - Pattern of the names of the files to follow
+    ///
+    pub fn new<P: Into<PathBuf>>(dir: P, pattern: &str) -> WatchConfig {
+        let regex = pattern
+            .chars()
+            .map(|c| match c {
+                '*' => ".*".to_string(),
+                '?' => ".".to_string(),
+                _ => regex::escape(&c.to_string()),
+            })
+            .collect::<String>();
+
+        WatchConfig {
+            dir: dir.into(),
+            pattern: Regex::new(&format!("^{regex}$")).expect("escaped pattern is always valid"),
+        }
+    }
+
+    /// Returns true if the file with the passed name should be followed.
+    pub fn is_match(&self, name: &OsStr) -> bool {
+        name.to_str()
+            .is_some_and(|name| self.pattern.is_match(name))
+    }
+}
+
+/// A file being followed and the position up to which it has been read
+struct FollowedFile {
+    path: PathBuf,
+    offset: u64,
+    line_no: usize,
+    /// Start of a line that is still being written
+    partial: Vec<u8>,
+}
+
+impl FollowedFile {
+    fn new(path: PathBuf, offset: u64) -> Self {
+        Self {
+            path,
+            offset,
+            line_no: 0,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Returns the complete lines written to the file since it was last read along with
+    /// their line numbers. Line numbers are counted from where following started. A file
+    /// that has been truncated is read again from its start.
+    fn read_new_lines(&mut self) -> IoResult<Vec<(usize, String)>> {
+        let mut file = File::open(&self.path)?;
+
+        if file.metadata()?.len() < self.offset {
+            self.offset = 0;
+            self.line_no = 0;
+            self.partial.clear();
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let read = file.read_to_end(&mut self.partial)?;
+        self.offset += read as u64;
+
+        let complete = match self.partial.iter().rposition(|byte| *byte == b'\n') {
+            Some(newline_pos) => {
+                let rest = self.partial.split_off(newline_pos + 1);
+                std::mem::replace(&mut self.partial, rest)
+            }
+            None => return Ok(Vec::new()),
+        };
+
+        Ok(String::from_utf8_lossy(&complete)
+            .lines()
+            .map(|line| {
+                self.line_no += 1;
+                (self.line_no, line.to_string())
+            })
+            .collect())
+    }
+}
+
+/// Follows every file in a directory whose name matches a pattern, including files created
+/// after watching started, and writes the records appended to them as they arrive. Output
+/// is labelled with the name of the file it came from in the manner of `tail -f`, with a
+/// `==> name <==` line written whenever the file being written changes. Files that already
+/// exist are followed from their end, while new files are read from their start.
+///
+pub struct DirWatcher {
+    config: WatchConfig,
+    files: BTreeMap<OsString, FollowedFile>,
+    last_label: Option<OsString>,
+}
+
+impl DirWatcher {
+    /// Creates a new watcher following the matching files currently in the directory.
+    ///
+    /// # Errors
+    ///
+    /// If the directory can't be read, an IO error is returned.
+    ///
+    pub fn new(config: WatchConfig) -> IoResult<DirWatcher> {
+        let mut watcher = DirWatcher {
+            config,
+            files: BTreeMap::new(),
+            last_label: None,
+        };
+
+        for entry in fs::read_dir(&watcher.config.dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if entry.file_type()?.is_file() && watcher.config.is_match(&name) {
+                let length = entry.metadata()?.len();
+                let path = entry.path();
+                watcher.files.insert(name, FollowedFile::new(path, length));
+            }
+        }
+
+        Ok(watcher)
+    }
+
+    /// Watches the directory and writes the records appended to the followed files until
+    /// the process is terminated or, when `--head` is configured, until enough matching
+    /// records have been written. The records of every file pass through a single filtering
+    /// stage, so context, collapsing, sampling and rate limiting apply across batches of
+    /// appended lines rather than being reset for each of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Write implementation to output data to
+    /// * `output_config` - Configuration containing the filters and output format
+    ///
+    /// # Errors
+    ///
+    /// If the directory can't be watched, an IO error is returned.
+    ///
+    #[cfg(target_os = "linux")]
+    pub fn watch<W: Write>(
+        &mut self,
+        writer: &mut W,
+        output_config: &LoggerOutputConfig,
+    ) -> IoResult<()> {
+        use inotify::{EventMask, Inotify, WatchMask};
+
+        let mut inotify = Inotify::init()?;
+        inotify.watches().add(
+            &self.config.dir,
+            WatchMask::CREATE
+                | WatchMask::MODIFY
+                | WatchMask::MOVED_TO
+                | WatchMask::MOVED_FROM
+                | WatchMask::DELETE,
+        )?;
+
+        let mut pipeline = RecordPipeline::new(output_config);
+        let mut buffer = [0u8; 4096];
+        loop {
+            let events: Vec<(EventMask, Option<OsString>)> = inotify
+                .read_events_blocking(&mut buffer)?
+                .map(|event| (event.mask, event.name.map(OsStr::to_os_string)))
+                .collect();
+
+            for (mask, name) in events {
+                if mask.contains(EventMask::Q_OVERFLOW) {
+                    // Events were lost, so check every file for new data
+                    let names: Vec<OsString> = fs::read_dir(&self.config.dir)?
+                        .filter_map(|entry| entry.ok().map(|entry| entry.file_name()))
+                        .collect();
+                    for name in names {
+                        self.write_new_lines(name, writer, &mut pipeline, output_config);
+                    }
+                    continue;
+                }
+
+                let name = match name.filter(|name| self.config.is_match(name)) {
+                    Some(name) => name,
+                    None => continue,
+                };
+
+                if mask.intersects(EventMask::DELETE | EventMask::MOVED_FROM) {
+                    self.files.remove(&name);
+                } else {
+                    if mask.intersects(EventMask::CREATE | EventMask::MOVED_TO) {
+                        self.files.remove(&name);
+                    }
+                    self.write_new_lines(name, writer, &mut pipeline, output_config);
+                }
+            }
+
+            writer.flush()?;
+
+            if pipeline.is_done(output_config) {
+                return Ok(());
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn watch<W: Write>(
+        &mut self,
+        _writer: &mut W,
+        _output_config: &LoggerOutputConfig,
+    ) -> IoResult<()> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "watching directories is only supported on Linux",
+        ))
+    }
+
+    /// Writes the lines appended to the named file, following it from its start if it
+    /// isn't already being followed. Files that can't be read (eg because they were removed)
+    /// are ignored.
+    fn write_new_lines<W: Write>(
+        &mut self,
+        name: OsString,
+        writer: &mut W,
+        pipeline: &mut RecordPipeline,
+        output_config: &LoggerOutputConfig,
+    ) {
+        let path = self.config.dir.join(&name);
+        if !path.is_file() || !self.config.is_match(&name) {
+            return;
+        }
+
+        let file = self
+            .files
+            .entry(name.clone())
+            .or_insert_with(|| FollowedFile::new(path, 0));
+
+        let lines = match file.read_new_lines() {
+            Ok(lines) if !lines.is_empty() => lines,
+            _ => return,
+        };
+
+        if self.last_label.as_ref() != Some(&name) {
+            if self.last_label.is_some() {
+                pipeline.end_input(writer, output_config);
+            }
+            write_label(writer, &name, output_config);
+            self.last_label = Some(name);
+        }
+
+        for (line_no, line) in lines {
+            pipeline.write_line(writer, line_no, line, output_config);
+        }
+    }
+}
+
+/// Writes the line identifying the file that the following records came from. For
+/// structured output, the label is written to stderr so that the output can still be parsed.
+fn write_label<W: Write>(writer: &mut W, name: &OsStr, output_config: &LoggerOutputConfig) {
+    let label = format!("==> {} <==", name.to_string_lossy());
+
+    if output_config.format.is_structured() {
+        wln!(std::io::stderr(), "{}", label);
+    } else if output_config.format == LogFormat::Html {
+        write_html_text(writer, &label, "note");
+    } else {
+        wln!(writer, "{}", label.bold());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+
+    #[test]
+    fn can_match_file_name_patterns() {
+        let config = WatchConfig::new("/tmp", "job-*.log");
+        assert!(config.is_match(OsStr::new("job-42.log")));
+        assert!(!config.is_match(OsStr::new("job-42.log.gz")));
+        assert!(!config.is_match(OsStr::new("jobs.log")));
+        assert!(WatchConfig::new("/tmp", "app.?").is_match(OsStr::new("app.1")));
+    }
+
+    #[test]
+    fn can_read_lines_appended_to_file() {
+        let dir = std::env::temp_dir().join(format!("bunyan-watch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("job.log");
+        fs::write(&path, "one\ntw").unwrap();
+
+        let mut file = FollowedFile::new(path.clone(), 0);
+        assert_eq!(file.read_new_lines().unwrap(), vec![(1, "one".to_string())]);
+
+        let mut writer = OpenOptions::new().append(true).open(&path).unwrap();
+        writer.write_all(b"o\nthree\n").unwrap();
+        assert_eq!(
+            file.read_new_lines().unwrap(),
+            vec![(2, "two".to_string()), (3, "three".to_string())]
+        );
+
+        fs::write(&path, "new\n").unwrap();
+        assert_eq!(file.read_new_lines().unwrap(), vec![(1, "new".to_string())]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn context_continues_across_batches() {
+        let dir = std::env::temp_dir().join(format!("bunyan-watch-ctx-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("job.log");
+        let record = |level: u16, msg: &str| {
+            format!(
+                "{{\"name\":\"job\",\"hostname\":\"host\",\"pid\":1,\"level\":{level},\"msg\":\"{msg}\",\"time\":\"2020-01-01T00:00:00Z\",\"v\":0}}\n"
+            )
+        };
+
+        let output_config = LoggerOutputConfig {
+            level: Some(crate::LevelFilter::parse("error").unwrap()),
+            context: Some(crate::ContextConfig {
+                before: 0,
+                after: 1,
+                time_window: None,
+            }),
+            format: LogFormat::Json(0),
+            ..Default::default()
+        };
+
+        let mut watcher = DirWatcher::new(WatchConfig::new(&dir, "*.log")).unwrap();
+        let mut pipeline = RecordPipeline::new(&output_config);
+        let mut writer: Vec<u8> = Vec::new();
+        let name = OsString::from("job.log");
+
+        fs::write(&path, record(50, "failed")).unwrap();
+        watcher.write_new_lines(name.clone(), &mut writer, &mut pipeline, &output_config);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(record(30, "after").as_bytes()).unwrap();
+        file.write_all(record(30, "skipped").as_bytes()).unwrap();
+        watcher.write_new_lines(name, &mut writer, &mut pipeline, &output_config);
+
+        let output = String::from_utf8(writer).unwrap();
+        assert!(output.contains("failed"));
+        assert!(output.contains("after"));
+        assert!(!output.contains("skipped"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -16,7 +16,7 @@ pub struct DividerWriter<'a, W: Write> {
 }
 
 impl<'a, W: Write> DividerWriter<'a, W> {
-    pub fn new(inner: &'a mut W, divider_written: bool) -> DividerWriter<W> {
+    pub fn new(inner: &'a mut W, divider_written: bool) -> DividerWriter<'a, W> {
         DividerWriter {
             inner,
             divider_written,
@@ -0,0 +1,291 @@
+use std::io::Write;
+
+use chrono::SecondsFormat;
+use serde_json::map::Map;
+use serde_json::{json, Value};
+
+use crate::{BunyanLine, LogLevel};
+
+/// Version of the Elastic Common Schema that records are mapped onto
+const ECS_VERSION: &str = "8.11.0";
+/// Field holding the fields of a record that have no counterpart in ECS
+const CUSTOM_FIELD: &str = "bunyan";
+
+/// Writes a log record as a single line Elastic Common Schema document. The core Bunyan
+/// fields are mapped onto `@timestamp`, `message`, `log.level`, `log.logger`, `host.hostname`
+/// and `process.pid`, while the `req`, `res` and `err` objects written by the standard
+/// serializers are mapped onto the `http`, `url`, `client`, `user_agent` and `error` fields.
+/// The fields that have no counterpart in ECS are kept under `bunyan`. When a bulk index is
+/// given, each document is preceded by an Elasticsearch `_bulk` API action creating it in
+/// that index.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `log` - Log record to write
+/// * `bulk_index` - Optional index to create the document in with the `_bulk` API
+///
+pub fn write_ecs_line<W: Write>(writer: &mut W, log: &BunyanLine, bulk_index: Option<&str>) {
+    if let Some(index) = bulk_index {
+        wln!(writer, "{}", json!({ "create": { "_index": index } }));
+    }
+
+    let mut other = log.other.clone();
+    let mut document = Map::new();
+
+    document.insert(
+        "@timestamp".to_string(),
+        json!(log.time.to_rfc3339_opts(SecondsFormat::Millis, true)),
+    );
+    document.insert(
+        "log".to_string(),
+        json!({
+            "level": LogLevel::from(log.level).as_string().to_ascii_lowercase(),
+            "logger": log.name,
+        }),
+    );
+    document.insert("message".to_string(), json!(log.msg));
+    document.insert("ecs".to_string(), json!({ "version": ECS_VERSION }));
+    document.insert("service".to_string(), json!({ "name": log.name }));
+    document.insert("host".to_string(), json!({ "hostname": log.hostname }));
+    document.insert("process".to_string(), json!({ "pid": log.pid }));
+    document.insert("event".to_string(), json!({ "severity": log.level }));
+
+    if let Some(req) = take_object(&mut other, "req") {
+        map_req(&mut document, req);
+    }
+    if let Some(res) = take_object(&mut other, "res") {
+        map_res(&mut document, res);
+    }
+    if let Some(err) = take_object(&mut other, "err") {
+        map_err(&mut document, err);
+    }
+
+    // Fields logged by restify's audit logger and by tracing libraries
+    move_field(
+        &mut other,
+        "req_id",
+        &mut document,
+        &["http", "request", "id"],
+    );
+    move_field(
+        &mut other,
+        "remoteAddress",
+        &mut document,
+        &["client", "address"],
+    );
+    move_field(&mut other, "remotePort", &mut document, &["client", "port"]);
+    move_field(&mut other, "trace_id", &mut document, &["trace", "id"]);
+    move_field(&mut other, "span_id", &mut document, &["span", "id"]);
+    if let Some(latency) = other.get("latency").and_then(Value::as_f64) {
+        // The latency is logged in milliseconds while the duration is in nanoseconds
+        insert_path(
+            &mut document,
+            &["event", "duration"],
+            json!((latency * 1_000_000.0) as u64),
+        );
+    }
+
+    if let Some(component) = &log.component {
+        insert_path(
+            &mut document,
+            &[CUSTOM_FIELD, "component"],
+            json!(component),
+        );
+    }
+    for (key, value) in other {
+        insert_path(&mut document, &[CUSTOM_FIELD, &key], value);
+    }
+
+    wln!(writer, "{}", Value::Object(document));
+}
+
+/// Maps the fields of a request written by the standard `req` serializer onto ECS.
+fn map_req(document: &mut Map<String, Value>, mut req: Map<String, Value>) {
+    move_field(&mut req, "method", document, &["http", "request", "method"]);
+    move_field(&mut req, "url", document, &["url", "original"]);
+    move_field(&mut req, "httpVersion", document, &["http", "version"]);
+    move_field(&mut req, "remoteAddress", document, &["client", "address"]);
+    move_field(&mut req, "remotePort", document, &["client", "port"]);
+
+    if let Some(Value::Object(headers)) = req.get_mut("headers") {
+        if let Some(user_agent) = headers.remove("user-agent") {
+            insert_path(document, &["user_agent", "original"], user_agent);
+        }
+        if let Some(referrer) = headers.remove("referer") {
+            insert_path(document, &["http", "request", "referrer"], referrer);
+        }
+    }
+
+    insert_remainder(document, "req", req);
+}
+
+/// Maps the fields of a response written by the standard `res` serializer onto ECS.
+fn map_res(document: &mut Map<String, Value>, mut res: Map<String, Value>) {
+    if let Some(status_code) = res.remove("statusCode") {
+        // Status codes are sometimes logged as strings
+        let status_code = match &status_code {
+            Value::String(code) => code.parse::<u16>().map(|code| json!(code)).ok(),
+            _ => Some(status_code),
+        };
+        if let Some(status_code) = status_code {
+            insert_path(document, &["http", "response", "status_code"], status_code);
+        }
+    }
+
+    let content_length = res
+        .get("headers")
+        .and_then(|headers| headers.get("content-length"))
+        .and_then(|length| match length {
+            Value::String(length) => length.parse::<u64>().ok(),
+            _ => length.as_u64(),
+        });
+    if let Some(content_length) = content_length {
+        insert_path(
+            document,
+            &["http", "response", "body", "bytes"],
+            json!(content_length),
+        );
+    }
+
+    insert_remainder(document, "res", res);
+}
+
+/// Maps the fields of an error written by the standard `err` serializer onto ECS.
+fn map_err(document: &mut Map<String, Value>, mut err: Map<String, Value>) {
+    move_field(&mut err, "message", document, &["error", "message"]);
+    move_field(&mut err, "name", document, &["error", "type"]);
+    move_field(&mut err, "stack", document, &["error", "stack_trace"]);
+    move_field(&mut err, "code", document, &["error", "code"]);
+
+    insert_remainder(document, "err", err);
+}
+
+/// Removes and returns the object at the key of the record. Values other than objects are
+/// left in the record.
+fn take_object(record: &mut Map<String, Value>, key: &str) -> Option<Map<String, Value>> {
+    match record.remove(key) {
+        Some(Value::Object(object)) => Some(object),
+        Some(value) => {
+            record.insert(key.to_string(), value);
+            None
+        }
+        None => None,
+    }
+}
+
+/// Moves the value at the key of the source, if it is present and not null, to the path of
+/// the document. Existing values in the document are left as they are.
+fn move_field(
+    source: &mut Map<String, Value>,
+    key: &str,
+    document: &mut Map<String, Value>,
+    path: &[&str],
+) {
+    let already_set = lookup(document, path).is_some();
+
+    match source.get(key) {
+        Some(Value::Null) | None => (),
+        Some(_) if already_set => {
+            source.remove(key);
+        }
+        Some(_) => {
+            let value = source.remove(key).unwrap();
+            insert_path(document, path, value);
+        }
+    }
+}
+
+/// Keeps the fields of a serialized object that weren't mapped onto ECS under `bunyan`.
+fn insert_remainder(document: &mut Map<String, Value>, key: &str, remainder: Map<String, Value>) {
+    if !remainder.is_empty() {
+        insert_path(document, &[CUSTOM_FIELD, key], Value::Object(remainder));
+    }
+}
+
+/// Returns the value at the path of the document if it is present.
+fn lookup<'a>(document: &'a Map<String, Value>, path: &[&str]) -> Option<&'a Value> {
+    let (last, parents) = path.split_last()?;
+    let mut current = document;
+
+    for parent in parents {
+        current = current.get(*parent)?.as_object()?;
+    }
+
+    current.get(*last)
+}
+
+/// Inserts the value at the path of the document, creating the objects along the path.
+fn insert_path(document: &mut Map<String, Value>, path: &[&str], value: Value) {
+    match path {
+        [] => (),
+        [last] => {
+            document.insert(last.to_string(), value);
+        }
+        [first, rest @ ..] => {
+            let nested = document
+                .entry(first.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+
+            if !nested.is_object() {
+                *nested = Value::Object(Map::new());
+            }
+
+            if let Value::Object(nested) = nested {
+                insert_path(nested, rest, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(json: &str) -> Map<String, Value> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn can_map_serialized_requests() {
+        let mut mapped = Map::new();
+        map_req(
+            &mut mapped,
+            document(
+                r#"{"method": "GET", "url": "/a?b=c", "httpVersion": "1.1",
+                    "headers": {"user-agent": "curl/8.0", "referer": "http://x/", "host": "y"}}"#,
+            ),
+        );
+
+        assert_eq!(
+            Value::Object(mapped).to_string(),
+            concat!(
+                r#"{"http":{"request":{"method":"GET","referrer":"http://x/"},"version":"1.1"},"#,
+                r#""url":{"original":"/a?b=c"},"user_agent":{"original":"curl/8.0"},"#,
+                r#""bunyan":{"req":{"headers":{"host":"y"}}}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn can_map_serialized_responses_and_errors() {
+        let mut mapped = Map::new();
+        map_res(
+            &mut mapped,
+            document(r#"{"statusCode": "404", "headers": {"content-length": "12"}}"#),
+        );
+        map_err(
+            &mut mapped,
+            document(r#"{"message": "boom", "name": "Error", "stack": "Error: boom"}"#),
+        );
+
+        assert_eq!(
+            Value::Object(mapped).to_string(),
+            concat!(
+                r#"{"http":{"response":{"status_code":404,"body":{"bytes":12}}},"#,
+                r#""bunyan":{"res":{"headers":{"content-length":"12"}}},"#,
+                r#""error":{"message":"boom","type":"Error","stack_trace":"Error: boom"}}"#
+            )
+        );
+    }
+}
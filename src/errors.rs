@@ -176,3 +176,295 @@ impl From<::serde_json::Error> for Kind {
         Kind::Json(error)
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct ScriptCompileError {
+    pub script: String,
+    pub msg: String,
+}
+
+impl ScriptCompileError {
+    pub fn new<S, M>(script: S, msg: M) -> ScriptCompileError
+    where
+        S: Into<String>,
+        M: Into<String>,
+    {
+        ScriptCompileError {
+            script: script.into(),
+            msg: msg.into(),
+        }
+    }
+}
+
+impl fmt::Display for ScriptCompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Unable to compile expression [{}]: {}",
+            self.script, self.msg
+        )
+    }
+}
+
+impl StdError for ScriptCompileError {
+    fn description(&self) -> &str {
+        self.msg.as_str()
+    }
+
+    fn cause(&self) -> Option<&dyn StdError> {
+        None // there is no causing error
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldFilterParseError {
+    pub input: String,
+    pub msg: String,
+}
+
+impl FieldFilterParseError {
+    pub fn new<S, M>(input: S, msg: M) -> FieldFilterParseError
+    where
+        S: Into<String>,
+        M: Into<String>,
+    {
+        FieldFilterParseError {
+            input: input.into(),
+            msg: msg.into(),
+        }
+    }
+}
+
+impl fmt::Display for FieldFilterParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Unable to parse field filter [{}]: {}",
+            self.input, self.msg
+        )
+    }
+}
+
+impl StdError for FieldFilterParseError {
+    fn description(&self) -> &str {
+        self.msg.as_str()
+    }
+
+    fn cause(&self) -> Option<&dyn StdError> {
+        None // there is no causing error
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DurationParseError {
+    pub input: String,
+}
+
+impl From<String> for DurationParseError {
+    fn from(s: String) -> Self {
+        DurationParseError { input: s }
+    }
+}
+
+impl fmt::Display for DurationParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Unable to parse duration from input value (eg 500ms, 5s, 2m, 1h): {}",
+            self.input
+        )
+    }
+}
+
+impl StdError for DurationParseError {
+    fn description(&self) -> &str {
+        "Unable to parse duration from input value"
+    }
+
+    fn cause(&self) -> Option<&dyn StdError> {
+        None // there is no causing error
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RateParseError {
+    pub input: String,
+    pub msg: String,
+}
+
+impl RateParseError {
+    pub fn new<S, M>(input: S, msg: M) -> RateParseError
+    where
+        S: Into<String>,
+        M: Into<String>,
+    {
+        RateParseError {
+            input: input.into(),
+            msg: msg.into(),
+        }
+    }
+}
+
+impl fmt::Display for RateParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse rate [{}]: {}", self.input, self.msg)
+    }
+}
+
+impl StdError for RateParseError {
+    fn description(&self) -> &str {
+        self.msg.as_str()
+    }
+
+    fn cause(&self) -> Option<&dyn StdError> {
+        None // there is no causing error
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TimeRangeParseError {
+    pub input: String,
+    pub msg: String,
+}
+
+impl TimeRangeParseError {
+    pub fn new<S, M>(input: S, msg: M) -> TimeRangeParseError
+    where
+        S: Into<String>,
+        M: Into<String>,
+    {
+        TimeRangeParseError {
+            input: input.into(),
+            msg: msg.into(),
+        }
+    }
+}
+
+impl fmt::Display for TimeRangeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse time [{}]: {}", self.input, self.msg)
+    }
+}
+
+impl StdError for TimeRangeParseError {
+    fn description(&self) -> &str {
+        self.msg.as_str()
+    }
+
+    fn cause(&self) -> Option<&dyn StdError> {
+        None // there is no causing error
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TemplateParseError {
+    pub template: String,
+    pub msg: String,
+}
+
+impl TemplateParseError {
+    pub fn new<S, M>(template: S, msg: M) -> TemplateParseError
+    where
+        S: Into<String>,
+        M: Into<String>,
+    {
+        TemplateParseError {
+            template: template.into(),
+            msg: msg.into(),
+        }
+    }
+}
+
+impl fmt::Display for TemplateParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Unable to parse template [{}]: {}",
+            self.template, self.msg
+        )
+    }
+}
+
+impl StdError for TemplateParseError {
+    fn description(&self) -> &str {
+        self.msg.as_str()
+    }
+
+    fn cause(&self) -> Option<&dyn StdError> {
+        None // there is no causing error
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldListParseError {
+    pub fields: String,
+    pub msg: String,
+}
+
+impl FieldListParseError {
+    pub fn new<S, M>(fields: S, msg: M) -> FieldListParseError
+    where
+        S: Into<String>,
+        M: Into<String>,
+    {
+        FieldListParseError {
+            fields: fields.into(),
+            msg: msg.into(),
+        }
+    }
+}
+
+impl fmt::Display for FieldListParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Unable to parse field list [{}]: {}",
+            self.fields, self.msg
+        )
+    }
+}
+
+impl StdError for FieldListParseError {
+    fn description(&self) -> &str {
+        self.msg.as_str()
+    }
+
+    fn cause(&self) -> Option<&dyn StdError> {
+        None // there is no causing error
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StateFileError {
+    pub path: String,
+    pub msg: String,
+}
+
+impl StateFileError {
+    pub fn new<S, M>(path: S, msg: M) -> StateFileError
+    where
+        S: Into<String>,
+        M: Into<String>,
+    {
+        StateFileError {
+            path: path.into(),
+            msg: msg.into(),
+        }
+    }
+}
+
+impl fmt::Display for StateFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to use state file [{}]: {}", self.path, self.msg)
+    }
+}
+
+impl StdError for StateFileError {
+    fn description(&self) -> &str {
+        self.msg.as_str()
+    }
+
+    fn cause(&self) -> Option<&dyn StdError> {
+        None // there is no causing error
+    }
+}
@@ -0,0 +1,221 @@
+use std::fmt;
+
+use regex::Regex;
+use serde_json::map::Map;
+use serde_json::Value;
+
+use crate::errors::FieldFilterParseError;
+
+/// Comparison applied to the value found at a field path
+#[derive(Debug, Clone)]
+enum FieldOperator {
+    /// `key=value` - the field's value is equal to the given text
+    Equal(String),
+    /// `key!=value` - the field is not present or its value differs from the given text
+    NotEqual(String),
+    /// `key~regex` - the field's value matches the given regular expression
+    Matches(Regex),
+}
+
+/// A single `key<op>value` expression
+#[derive(Debug, Clone)]
+struct FieldMatcher {
+    path: String,
+    operator: FieldOperator,
+}
+
+impl FieldMatcher {
+    /// Parses an expression in the form of `key=value`, `key!=value` or `key~regex`.
+    ///
+    /// # Errors
+    ///
+    /// If no operator is present, the key is empty or the regular expression is invalid,
+    /// a `FieldFilterParseError` is returned.
+    ///
+    fn parse(input: &str) -> Result<FieldMatcher, FieldFilterParseError> {
+        let operator_pos = input
+            .find(['=', '!', '~'])
+            .ok_or_else(|| FieldFilterParseError::new(input, "expected one of =, != or ~"))?;
+        let (path, rest) = input.split_at(operator_pos);
+
+        if path.is_empty() {
+            return Err(FieldFilterParseError::new(input, "field name is empty"));
+        }
+
+        let operator = if let Some(value) = rest.strip_prefix("!=") {
+            FieldOperator::NotEqual(value.to_string())
+        } else if let Some(value) = rest.strip_prefix('=') {
+            FieldOperator::Equal(value.to_string())
+        } else if let Some(pattern) = rest.strip_prefix('~') {
+            let regex = Regex::new(pattern)
+                .map_err(|e| FieldFilterParseError::new(input, e.to_string()))?;
+            FieldOperator::Matches(regex)
+        } else {
+            return Err(FieldFilterParseError::new(
+                input,
+                "expected one of =, != or ~",
+            ));
+        };
+
+        Ok(FieldMatcher {
+            path: path.to_string(),
+            operator,
+        })
+    }
+
+    fn is_match(&self, record: &Map<String, Value>) -> bool {
+        let value = lookup_path(record, &self.path).map(|value| string_or_value!(value));
+
+        match (&self.operator, value) {
+            (FieldOperator::Equal(expected), Some(actual)) => *expected == actual,
+            (FieldOperator::NotEqual(expected), Some(actual)) => *expected != actual,
+            (FieldOperator::NotEqual(_), None) => true,
+            (FieldOperator::Matches(regex), Some(actual)) => regex.is_match(&actual),
+            (_, None) => false,
+        }
+    }
+}
+
+/// Filters log records by comparing the values of fields to literal values or regular
+/// expressions without the need to evaluate JS. Fields are referenced by dotted paths
+/// (eg `req.url` or `err.code`) and a record is only matched when all expressions match.
+///
+#[derive(Clone)]
+pub struct FieldFilter {
+    matchers: Vec<FieldMatcher>,
+}
+
+impl FieldFilter {
+    pub fn new<I, S>(expressions: I) -> Result<Self, FieldFilterParseError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let matchers = expressions
+            .into_iter()
+            .map(|expression| FieldMatcher::parse(expression.as_ref()))
+            .collect::<Result<Vec<FieldMatcher>, FieldFilterParseError>>()?;
+
+        Ok(Self { matchers })
+    }
+
+    /// Returns true if the passed record satisfies all field expressions.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - Map containing the JSON data of the log record
+    ///
+    pub fn filter(&self, record: &Map<String, Value>) -> bool {
+        self.matchers.iter().all(|matcher| matcher.is_match(record))
+    }
+
+    /// Returns the value that the field at the passed path must be equal to for a record to
+    /// satisfy the filter, if there is an equality expression for the path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Dotted path of the field
+    ///
+    pub fn required_value(&self, path: &str) -> Option<&str> {
+        self.matchers
+            .iter()
+            .filter(|matcher| matcher.path == path)
+            .find_map(|matcher| match &matcher.operator {
+                FieldOperator::Equal(expected) => Some(expected.as_str()),
+                _ => None,
+            })
+    }
+}
+
+impl fmt::Debug for FieldFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let paths: Vec<&str> = self.matchers.iter().map(|m| m.path.as_str()).collect();
+        write!(f, "FieldFilter [{}]", paths.join(", "))
+    }
+}
+
+/// Returns the value found by following the passed dotted path (eg `req.headers.host`)
+/// through the nested JSON objects of a record. Numeric path segments may be used to
+/// index into arrays.
+///
+/// # Arguments
+///
+/// * `record` - Map containing the JSON data of the log record
+/// * `path` - Dot separated list of keys
+///
+pub fn lookup_path<'a>(record: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+    let mut segments = path.split('.');
+    let mut current = record.get(segments.next()?)?;
+
+    for segment in segments {
+        current = match current {
+            Value::Object(map) => map.get(segment)?,
+            Value::Array(array) => array.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    Some(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> Map<String, Value> {
+        serde_json::from_str(
+            r#"{"level": 30, "component": "db", "req_id": 123,
+                "req": {"method": "GET", "url": "/users/42", "headers": {"host": "example.com"}},
+                "err": {"code": "ECONNRESET"}}"#,
+        )
+        .expect("invalid test JSON")
+    }
+
+    fn is_match(expressions: Vec<&str>) -> bool {
+        FieldFilter::new(expressions).unwrap().filter(&record())
+    }
+
+    #[test]
+    fn can_match_equality() {
+        assert!(is_match(vec!["component=db"]));
+        assert!(is_match(vec!["req_id=123"]));
+        assert!(is_match(vec!["req.headers.host=example.com"]));
+        assert!(!is_match(vec!["component=http"]));
+        assert!(!is_match(vec!["missing=value"]));
+    }
+
+    #[test]
+    fn can_match_inequality() {
+        assert!(is_match(vec!["component!=http"]));
+        assert!(is_match(vec!["missing!=value"]));
+        assert!(!is_match(vec!["err.code!=ECONNRESET"]));
+    }
+
+    #[test]
+    fn can_match_regex() {
+        assert!(is_match(vec!["req.url~^/users/\\d+$"]));
+        assert!(!is_match(vec!["req.url~^/groups"]));
+        assert!(!is_match(vec!["missing~.*"]));
+    }
+
+    #[test]
+    fn multiple_expressions_are_anded() {
+        assert!(is_match(vec!["component=db", "req.method=GET"]));
+        assert!(!is_match(vec!["component=db", "req.method=POST"]));
+    }
+
+    #[test]
+    fn can_find_required_values() {
+        let filter = FieldFilter::new(vec!["name!=db", "name=app", "req.method~GET"]).unwrap();
+        assert_eq!(filter.required_value("name"), Some("app"));
+        assert_eq!(filter.required_value("req.method"), None);
+        assert_eq!(filter.required_value("component"), None);
+    }
+
+    #[test]
+    fn invalid_expressions_are_errors() {
+        assert!(FieldFilter::new(vec!["component"]).is_err());
+        assert!(FieldFilter::new(vec!["=db"]).is_err());
+        assert!(FieldFilter::new(vec!["req.url~("]).is_err());
+    }
+}
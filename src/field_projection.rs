@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+
+use serde_json::map::Map;
+use serde_json::Value;
+
+use crate::errors::FieldListParseError;
+
+/// Fields of a Bunyan record that are always kept so that the output remains valid Bunyan
+const CORE_FIELDS: [&str; 7] = ["v", "level", "name", "hostname", "pid", "time", "msg"];
+
+/// Tree of the dotted paths to keep, in which a leaf keeps the whole value at its path
+#[derive(Debug, Clone, Default)]
+struct PathTree {
+    children: BTreeMap<String, PathTree>,
+    is_leaf: bool,
+}
+
+impl PathTree {
+    fn insert(&mut self, path: &[String]) {
+        match path.split_first() {
+            Some((first, rest)) => self.children.entry(first.clone()).or_default().insert(rest),
+            None => self.is_leaf = true,
+        }
+    }
+
+    /// Returns the fields of the object whose paths are in the tree, preserving the order
+    /// of the fields in the object.
+    fn project(&self, object: &Map<String, Value>) -> Map<String, Value> {
+        let mut projected = Map::new();
+
+        for (key, value) in object {
+            let child = match self.children.get(key) {
+                Some(child) => child,
+                None => continue,
+            };
+
+            if child.is_leaf {
+                projected.insert(key.clone(), value.clone());
+            } else if let Value::Object(nested) = value {
+                let nested = child.project(nested);
+                if !nested.is_empty() {
+                    projected.insert(key.clone(), Value::Object(nested));
+                }
+            }
+        }
+
+        projected
+    }
+}
+
+/// Limits the fields of the records written by the JSON output formats to the listed dotted
+/// paths (eg `req.url`) and removes the excluded paths. The core Bunyan fields (`v`, `level`,
+/// `name`, `hostname`, `pid`, `time` and `msg`) are always kept, so that the output can be
+/// read as Bunyan logs.
+///
+#[derive(Debug, Clone)]
+pub struct FieldProjection {
+    include: Option<PathTree>,
+    exclude: Vec<Vec<String>>,
+}
+
+impl FieldProjection {
+    /// Creates a new projection from comma separated lists of dotted paths.
+    ///
+    /// # Arguments
+    ///
+    /// * `fields` - Optional paths to keep, in addition to the core Bunyan fields
+    /// * `exclude_fields` - Optional paths to remove
+    ///
+    /// # Errors
+    ///
+    /// If a path is empty or has an empty segment, or if a core Bunyan field is excluded,
+    /// a `FieldListParseError` is returned.
+    ///
+    pub fn new(
+        fields: Option<&str>,
+        exclude_fields: Option<&str>,
+    ) -> Result<FieldProjection, FieldListParseError> {
+        let include = match fields {
+            Some(fields) => {
+                let mut tree = PathTree::default();
+                for field in CORE_FIELDS {
+                    tree.insert(&[field.to_string()]);
+                }
+                for path in parse_paths(fields)? {
+                    tree.insert(&path);
+                }
+                Some(tree)
+            }
+            None => None,
+        };
+
+        let exclude = match exclude_fields {
+            Some(exclude_fields) => parse_paths(exclude_fields)?,
+            None => Vec::new(),
+        };
+
+        if let Some(path) = exclude
+            .iter()
+            .find(|path| path.len() == 1 && CORE_FIELDS.contains(&path[0].as_str()))
+        {
+            return Err(FieldListParseError::new(
+                exclude_fields.unwrap_or_default(),
+                format!("[{}] is a core Bunyan field and can't be excluded", path[0]),
+            ));
+        }
+
+        Ok(FieldProjection { include, exclude })
+    }
+
+    /// Returns the record with only the listed fields and without the excluded fields.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - Map containing the JSON data of the log record
+    ///
+    pub fn project(&self, record: &Map<String, Value>) -> Map<String, Value> {
+        let mut projected = match &self.include {
+            Some(include) => include.project(record),
+            None => record.clone(),
+        };
+
+        for path in &self.exclude {
+            remove_path(&mut projected, path);
+        }
+
+        projected
+    }
+}
+
+/// Returns the dotted paths of a comma separated list split into their segments.
+fn parse_paths(fields: &str) -> Result<Vec<Vec<String>>, FieldListParseError> {
+    fields
+        .split(',')
+        .map(str::trim)
+        .map(|field| {
+            if field.split('.').any(str::is_empty) {
+                Err(FieldListParseError::new(
+                    fields,
+                    format!("[{field}] is not a valid field path"),
+                ))
+            } else {
+                Ok(field.split('.').map(str::to_string).collect())
+            }
+        })
+        .collect()
+}
+
+/// Removes the value at the path from the object if it is present.
+fn remove_path(object: &mut Map<String, Value>, path: &[String]) {
+    match path {
+        [] => (),
+        [last] => {
+            object.remove(last);
+        }
+        [first, rest @ ..] => {
+            if let Some(Value::Object(nested)) = object.get_mut(first) {
+                remove_path(nested, rest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> Map<String, Value> {
+        serde_json::from_str(
+            r#"{"name": "app", "hostname": "host", "pid": 1, "level": 30, "msg": "done",
+                "time": "2012-08-08T10:25:47.636Z", "v": 0, "req_id": 7,
+                "req": {"method": "GET", "url": "/a", "headers": {"host": "a"}},
+                "res": {"statusCode": 200, "headers": {"server": "b"}}}"#,
+        )
+        .expect("invalid test JSON")
+    }
+
+    #[test]
+    fn can_keep_listed_fields() {
+        let projection =
+            FieldProjection::new(Some("req.url,res.statusCode,missing"), None).unwrap();
+        let projected = Value::Object(projection.project(&record()));
+
+        assert_eq!(
+            projected.to_string(),
+            r#"{"name":"app","hostname":"host","pid":1,"level":30,"msg":"done","time":"2012-08-08T10:25:47.636Z","v":0,"req":{"url":"/a"},"res":{"statusCode":200}}"#
+        );
+    }
+
+    #[test]
+    fn can_exclude_fields() {
+        let projection =
+            FieldProjection::new(Some("req,res"), Some("req.headers, res.headers")).unwrap();
+        let projected = projection.project(&record());
+
+        assert_eq!(
+            projected["req"].to_string(),
+            r#"{"method":"GET","url":"/a"}"#
+        );
+        assert_eq!(projected["res"].to_string(), r#"{"statusCode":200}"#);
+        assert!(!projected.contains_key("req_id"));
+    }
+
+    #[test]
+    fn cannot_exclude_core_fields_or_empty_paths() {
+        assert!(FieldProjection::new(None, Some("req.headers,time")).is_err());
+        assert!(FieldProjection::new(Some("req..url"), None).is_err());
+        assert!(FieldProjection::new(Some("req_id,"), None).is_err());
+        assert!(FieldProjection::new(None, Some("req.time")).is_ok());
+    }
+}
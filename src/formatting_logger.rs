@@ -5,6 +5,8 @@ use crate::{BunyanLine, LogLevel, Logger, LoggerOutputConfig};
 
 use std::io::Write;
 
+use regex::Regex;
+
 use httpstatus::StatusCode;
 
 use serde_json::map::Map;
@@ -54,21 +56,43 @@ const GENERAL_RESERVED: [&str; 5] = ["req", "client_req", "res", "client_res", "
 /// Default assumed HTTP version
 const DEFAULT_HTTP_VERSION: &str = "1.1";
 
+/// Returns the text styled with the color used for the passed log level.
+pub(crate) fn level_color(level: &LogLevel, text: ColoredString) -> ColoredString {
+    match level {
+        LogLevel::DEBUG => text.yellow(),
+        LogLevel::INFO => text.cyan(),
+        LogLevel::WARN => text.magenta(),
+        LogLevel::ERROR => text.red(),
+        LogLevel::FATAL => text.reversed(),
+        LogLevel::TRACE | LogLevel::OTHER(_) => text,
+    }
+}
+
 /// Writes the src information of the log line if it is present.
 ///
 /// # Arguments
 ///
 /// * `writer` - Write implementation to output data to
 /// * `other` - Mutable map containing JSON optional JSON data. Keys will be removed as processed.
+/// * `highlighter` - Optional regular expression matching text to emphasize
 ///
-fn write_src<W: Write>(writer: &mut W, other: &mut Map<String, Value>) {
+fn write_src<W: Write>(
+    writer: &mut W,
+    other: &mut Map<String, Value>,
+    highlighter: Option<&Regex>,
+) {
     if let Some(ref src) = other.remove("src") {
         match src {
             Value::Object(map) => {
                 // We only display the src information if [src.file] is present
                 if let Some(ref file) = map.get("file") {
                     w!(writer, "{}", " (".green());
-                    w!(writer, "{}", string_or_value!(file).green());
+                    let file = string_or_value!(file);
+                    w!(
+                        writer,
+                        "{}",
+                        highlight_matches(&file, highlighter, &|text| text.green())
+                    );
 
                     if let Some(ref line) = map.get("line") {
                         w!(writer, ":{}", string_or_value!(line).green());
@@ -76,18 +100,120 @@ fn write_src<W: Write>(writer: &mut W, other: &mut Map<String, Value>) {
 
                     if let Some(ref func) = map.get("func") {
                         w!(writer, "{}", " in ");
-                        w!(writer, "{}", string_or_value!(func).bright_green());
+                        let func = string_or_value!(func);
+                        w!(
+                            writer,
+                            "{}",
+                            highlight_matches(&func, highlighter, &|text| text.bright_green())
+                        );
                     }
 
                     w!(writer, "{}", ")".green());
                 }
             }
-            Value::String(text) => w!(writer, " ({})", text),
+            Value::String(text) => w!(
+                writer,
+                " ({})",
+                highlight_matches(text, highlighter, &|text| text.normal())
+            ),
             _ => (),
         }
     }
 }
 
+/// Returns the passed text with the spans matched by the highlighter emphasized and the
+/// remaining text colorized using the passed function.
+///
+/// # Arguments
+///
+/// * `text` - Text to search for matches
+/// * `highlighter_option` - Optional regular expression matching the spans to emphasize
+/// * `colorize` - Function used to colorize text that is not matched
+///
+fn highlight_matches(
+    text: &str,
+    highlighter_option: Option<&Regex>,
+    colorize: &dyn Fn(&str) -> ColoredString,
+) -> String {
+    let highlighter = match highlighter_option {
+        Some(highlighter) => highlighter,
+        None => return colorize(text).to_string(),
+    };
+
+    let mut highlighted = String::with_capacity(text.len());
+    let mut last_end = 0;
+
+    for found in highlighter.find_iter(text) {
+        if found.start() == found.end() {
+            continue;
+        }
+
+        highlighted.push_str(&colorize(&text[last_end..found.start()]).to_string());
+        highlighted.push_str(&found.as_str().black().on_yellow().to_string());
+        last_end = found.end();
+    }
+
+    highlighted.push_str(&colorize(&text[last_end..]).to_string());
+    highlighted
+}
+
+/// Returns the passed text as a quoted JSON string with the text matching the highlighter
+/// emphasized. Matches are found in the unescaped text, so escape sequences added when quoting
+/// are never highlighted.
+///
+/// # Arguments
+///
+/// * `text` - Unescaped string value to quote
+/// * `highlighter` - Optional regular expression matching text to emphasize
+///
+fn highlight_quoted(text: &str, highlighter: Option<&Regex>) -> String {
+    let escape = |segment: &str| {
+        let quoted = Value::from(segment).to_string();
+        quoted[1..quoted.len() - 1].normal()
+    };
+
+    format!("\"{}\"", highlight_matches(text, highlighter, &escape))
+}
+
+/// Returns the passed value pretty printed as JSON in the same layout as
+/// `serde_json::to_string_pretty`, emphasizing the text matching the highlighter within string
+/// values only. Keys, numbers and punctuation are never highlighted.
+///
+/// # Arguments
+///
+/// * `value` - Value to pretty print
+/// * `highlighter` - Optional regular expression matching text to emphasize
+/// * `indent` - Indent of the line on which the value starts
+///
+fn highlight_pretty_json(value: &Value, highlighter: Option<&Regex>, indent: usize) -> String {
+    let inner = indent + 2;
+
+    match value {
+        Value::String(text) => highlight_quoted(text, highlighter),
+        Value::Array(array) if !array.is_empty() => {
+            let items: Vec<String> = array
+                .iter()
+                .map(|item| {
+                    let item = highlight_pretty_json(item, highlighter, inner);
+                    format!("{:inner$}{}", "", item)
+                })
+                .collect();
+            format!("[\n{}\n{:indent$}]", items.join(",\n"), "")
+        }
+        Value::Object(map) if !map.is_empty() => {
+            let entries: Vec<String> = map
+                .iter()
+                .map(|(key, item)| {
+                    let item = highlight_pretty_json(item, highlighter, inner);
+                    format!("{:inner$}{}: {}", "", Value::from(key.as_str()), item)
+                })
+                .collect();
+            format!("{{\n{}\n{:indent$}}}", entries.join(",\n"), "")
+        }
+        _ => value.to_string(),
+    }
+}
+
 /// Writes all of the extra parameters to the top line of output by iterating through the `others`
 /// map provided.
 ///
@@ -95,11 +221,13 @@ fn write_src<W: Write>(writer: &mut W, other: &mut Map<String, Value>) {
 /// * `writer` - Write implementation to output data to
 /// * `other` - Map containing all non-explicitly deserialized keys and values
 /// * `details` - Mutable vector containing strings to be written as output later
+/// * `highlighter` - Optional regular expression matching text to emphasize
 ///
 fn write_all_extra_params<W: Write>(
     writer: &mut W,
     other: &mut Map<String, Value>,
     details: &mut Vec<String>,
+    highlighter: Option<&Regex>,
 ) {
     /// Returns the passed value as a pretty printed JSON string with indents.
     ///
@@ -107,10 +235,20 @@ fn write_all_extra_params<W: Write>(
     /// * `key` - Key associated with value being processed
     /// * `value` - Value to be converted to a pretty printed string
     /// * `caller_option` - Optional name of top-level record (eg `req`, `res`, `err`, etc)
+    /// * `highlighter` - Optional regular expression matching text to emphasize
     ///
-    fn detail_pretty_print(key: &str, value: &Value, caller_option: Option<&str>) -> String {
-        let pretty =
-            ::serde_json::to_string_pretty(value).unwrap_or_else(|_| "[malformed]".to_string());
+    fn detail_pretty_print(
+        key: &str,
+        value: &Value,
+        caller_option: Option<&str>,
+        highlighter: Option<&Regex>,
+    ) -> String {
+        let pretty = match highlighter {
+            Some(_) => highlight_pretty_json(value, highlighter, 0),
+            None => {
+                ::serde_json::to_string_pretty(value).unwrap_or_else(|_| "[malformed]".to_string())
+            }
+        };
 
         match caller_option {
             Some(caller) => format!("{caller}.{key}: {pretty}"),
@@ -145,6 +283,21 @@ fn write_all_extra_params<W: Write>(
         string_or_value!(value)
     }
 
+    /// Returns the passed value formatted as by `quoteify` with the text matching the highlighter
+    /// emphasized within string values.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - a serde JSON value object to convert to a String
+    /// * `highlighter` - Optional regular expression matching text to emphasize
+    fn highlight_quoteified(value: &Value, highlighter: Option<&Regex>) -> String {
+        match value.as_str() {
+            Some(text) if text.contains(' ') => highlight_quoted(text, highlighter),
+            Some(text) => highlight_matches(text, highlighter, &|text| text.normal()),
+            None => quoteify(value),
+        }
+    }
+
     /// Returns an optional string representing the string presentation of an extra parameter. When
     /// a `None` value is returned, the value has been added to the `details` vector.
     ///
@@ -153,16 +306,19 @@ fn write_all_extra_params<W: Write>(
     /// * `value` - Value to be converted to a pretty printed string
     /// * `caller_option` - Optional name of top-level record (eg `req`, `res`, `err`, etc)
     /// * `details` - Mutable vector containing strings to be written as output later
+    /// * `highlighter` - Optional regular expression matching text to emphasize
     fn stringify(
         key: &str,
         value: &Value,
         caller_option: Option<&str>,
         details: &mut Vec<String>,
+        highlighter: Option<&Regex>,
     ) -> Option<String> {
         match value {
             Value::String(text) => {
                 // Add long strings to details
                 if is_multiline_string(text) {
+                    let text = highlight_matches(text, highlighter, &|text| text.normal());
                     let detail = match caller_option {
                         Some(caller) => format!("{caller}.{key}: {text}"),
                         None => format!("{key}: {text}"),
@@ -173,7 +329,7 @@ fn write_all_extra_params<W: Write>(
                     None
                 // Wrap strings with spaces in quotation marks
                 } else {
-                    Some(quoteify(value))
+                    Some(highlight_quoteified(value, highlighter))
                 }
             }
             Value::Number(_) => Some(string_or_value!(value)),
@@ -183,7 +339,7 @@ fn write_all_extra_params<W: Write>(
                 if map.is_empty() {
                     Some("{}".to_string())
                 } else {
-                    details.push(detail_pretty_print(key, value, caller_option));
+                    details.push(detail_pretty_print(key, value, caller_option, highlighter));
                     None
                 }
             }
@@ -191,7 +347,7 @@ fn write_all_extra_params<W: Write>(
                 if array.is_empty() {
                     Some("[]".to_string())
                 } else {
-                    details.push(detail_pretty_print(key, value, caller_option));
+                    details.push(detail_pretty_print(key, value, caller_option, highlighter));
                     None
                 }
             }
@@ -222,6 +378,7 @@ fn write_all_extra_params<W: Write>(
     /// * `is_first` - Mutable boolean indicating if the first parameter has been processed
     /// * `optional_node` - Optional Json object represented as `Value` containing parameters to be processed
     /// * `details` - Mutable vector containing strings to be written as output later
+    /// * `highlighter` - Optional regular expression matching text to emphasize
     /// * `exclude` - Closure in which when evaluated is true will exclude a given parameter
     ///
     fn write_params_for_object<W: Write>(
@@ -230,6 +387,7 @@ fn write_all_extra_params<W: Write>(
         is_first: &mut bool,
         node_option: Option<&Value>,
         details: &mut Vec<String>,
+        highlighter: Option<&Regex>,
         exclude: &dyn Fn(&str) -> bool,
     ) {
         if node_option.is_none() {
@@ -239,17 +397,18 @@ fn write_all_extra_params<W: Write>(
         let node = node_option.unwrap();
 
         // Display strings, numbers and null values, as-is
-        if caller_option.is_some()
-            && (node.is_string() || node.is_number() || node.is_null() || node.is_boolean())
-        {
-            write_formatting(writer, is_first);
-            w!(writer, "{}={}", caller_option.unwrap(), quoteify(node));
-            return;
+        if let Some(caller) = caller_option {
+            if node.is_string() || node.is_number() || node.is_null() || node.is_boolean() {
+                write_formatting(writer, is_first);
+                let text = highlight_quoteified(node, highlighter);
+                w!(writer, "{}={}", caller, text);
+                return;
+            }
         }
 
         if let Some(caller_option_value) = caller_option {
             if node.is_array() {
-                let value = stringify(caller_option_value, node, None, details);
+                let value = stringify(caller_option_value, node, None, details, highlighter);
                 if let Some(text) = value {
                     write_formatting(writer, is_first);
                     w!(writer, "{}={}\n", caller_option_value, text);
@@ -265,7 +424,7 @@ fn write_all_extra_params<W: Write>(
                 continue;
             }
 
-            let value: Option<String> = stringify(k, v, caller_option, details);
+            let value: Option<String> = stringify(k, v, caller_option, details, highlighter);
 
             if let Some(text) = value {
                 write_formatting(writer, is_first);
@@ -283,7 +442,10 @@ fn write_all_extra_params<W: Write>(
     // REQUEST ID [req_id] - special case we always write this first for visibility
     if let Some(req_id) = other.remove("req_id") {
         write_formatting(writer, &mut is_first);
-        w!(writer, "req_id={}", string_or_value!(req_id));
+        let text = highlight_matches(&string_or_value!(req_id), highlighter, &|text| {
+            text.normal()
+        });
+        w!(writer, "req_id={}", text);
     }
 
     /* Note: based on logic in write_params_for_object, parameters that do not fit
@@ -298,6 +460,7 @@ fn write_all_extra_params<W: Write>(
         &mut is_first,
         Some(&other_value),
         details,
+        highlighter,
         &|k: &str| GENERAL_RESERVED.contains(&k),
     );
 
@@ -312,6 +475,7 @@ fn write_all_extra_params<W: Write>(
         &mut is_first,
         other.get("req"),
         details,
+        highlighter,
         &|k: &str| REQ_RESERVED.contains(&k),
     );
 
@@ -322,6 +486,7 @@ fn write_all_extra_params<W: Write>(
         &mut is_first,
         other.get("client_req"),
         details,
+        highlighter,
         &|k: &str| CLIENT_REQ_RESERVED.contains(&k),
     );
 
@@ -332,6 +497,7 @@ fn write_all_extra_params<W: Write>(
         &mut is_first,
         other.get("res"),
         details,
+        highlighter,
         &|k: &str| RES_RESERVED.contains(&k),
     );
 
@@ -342,6 +508,7 @@ fn write_all_extra_params<W: Write>(
         &mut is_first,
         other.get("client_res"),
         details,
+        highlighter,
         &|k: &str| CLIENT_RES_RESERVED.contains(&k),
     );
 
@@ -352,6 +519,7 @@ fn write_all_extra_params<W: Write>(
         &mut is_first,
         other.get("err"),
         details,
+        highlighter,
         &|k: &str| ERR_RESERVED.contains(&k),
     );
 
@@ -366,8 +534,14 @@ fn write_all_extra_params<W: Write>(
 ///
 /// * `writer` - Write implementation to output data to
 /// * `other` - Map containing all non-explicitly deserialized keys and values
+/// * `highlighter` - Optional regular expression matching text to emphasize
 ///
-fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>) {
+fn write_req<W: Write>(
+    writer: &mut W,
+    key: &str,
+    other: &mut Map<String, Value>,
+    highlighter: Option<&Regex>,
+) {
     /// Writes the method, url and HTTP version associated with a request.
     ///
     /// # Arguments
@@ -375,6 +549,7 @@ fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
     /// * `writer` - Write implementation to output data to
     /// * `caller_name` - text indicating if we have been invoked from a "req" or "client_req" code path
     /// * `req_map` - Mutable map request data. Keys will be removed as processed.
+    /// * `highlighter` - Optional regular expression matching text to emphasize
     ///
     /// # Errors
     ///
@@ -386,12 +561,15 @@ fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
         writer: &mut W,
         caller: &str,
         req_map: &mut Map<String, Value>,
+        highlighter: Option<&Regex>,
     ) -> ParseResult {
         w!(writer, "{:indent$}", "", indent = BASE_INDENT_SIZE);
 
         if let Some(method) = req_map.remove("method") {
             if let Some(method_text) = method.as_str() {
-                w!(writer, "{} ", method_text.yellow());
+                let method_text =
+                    highlight_matches(method_text, highlighter, &|text| text.yellow());
+                w!(writer, "{} ", method_text);
             } else {
                 return Err(BunyanLogParseError::new(format!(
                     "[{caller}.method] is not a JSON string"
@@ -405,7 +583,8 @@ fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
 
         if let Some(url) = req_map.remove("url") {
             if let Some(url_text) = url.as_str() {
-                w!(writer, "{} ", url_text.bright_blue());
+                let url_text = highlight_matches(url_text, highlighter, &|text| text.bright_blue());
+                w!(writer, "{} ", url_text);
             } else {
                 return Err(BunyanLogParseError::new(format!(
                     "[{caller}.url] is not a JSON string"
@@ -453,7 +632,7 @@ fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
 
     // METHOD, URL, HTTP VERSION
     // If we can't parse a method, URL or Http Version from the request, output in JSON as is
-    if write_req_summary(writer, key, req_map).is_err() {
+    if write_req_summary(writer, key, req_map, highlighter).is_err() {
         wln!(writer, "undefined undefined HTTP/1.1");
         return;
     }
@@ -479,7 +658,7 @@ fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
 
     // HTTP HEADERS
     if let Some(headers) = req_map.remove("headers") {
-        write_headers(writer, &headers);
+        write_headers(writer, &headers, highlighter);
     }
 
     // HTTP BODY
@@ -488,6 +667,7 @@ fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
             let pretty = ::serde_json::to_string_pretty(&body_map)
                 .unwrap_or_else(|_| "[malformed]".to_string());
             for line in pretty.lines() {
+                let line = highlight_matches(line, highlighter, &|text| text.normal());
                 wln!(writer, "{:indent$}{}", "", line, indent = BASE_INDENT_SIZE);
             }
         } else {
@@ -496,7 +676,7 @@ fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
                 writer,
                 "{:indent$}{}",
                 "",
-                body_text,
+                highlight_matches(&body_text, highlighter, &|text| text.normal()),
                 indent = BASE_INDENT_SIZE
             );
         }
@@ -504,7 +684,7 @@ fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
 
     // HTTP TRAILER HEADERS
     if let Some(trailers) = req_map.remove("trailers") {
-        write_headers(writer, &trailers);
+        write_headers(writer, &trailers, highlighter);
     }
 }
 
@@ -569,8 +749,14 @@ fn json_string_or_number_as_u16(val: &Value) -> Result<u16, ParseIntFromJsonErro
 ///
 /// * `writer` - Write implementation to output data to
 /// * `other` - Map containing all non-explicitly deserialized keys and values
+/// * `highlighter` - Optional regular expression matching text to emphasize
 ///
-fn write_res<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>) {
+fn write_res<W: Write>(
+    writer: &mut W,
+    key: &str,
+    other: &mut Map<String, Value>,
+    highlighter: Option<&Regex>,
+) {
     /// Searches the passed map for the key `headers` and then `header` returning whichever
     /// is found first and is a valid string or JSON object. Otherwise, `None` is returned.
     fn find_headers(map: &mut Map<String, Value>) -> Option<Value> {
@@ -601,10 +787,7 @@ fn write_res<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
         option_http_version: Option<&str>,
     ) {
         let numeric_status_code = if let Some(json_value) = optional_code {
-            match json_string_or_number_as_u16(&json_value) {
-                Err(_) => None,
-                Ok(number) => Some(number),
-            }
+            json_string_or_number_as_u16(&json_value).ok()
         } else {
             None
         };
@@ -675,12 +858,13 @@ fn write_res<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
                     if line.is_empty() {
                         continue;
                     }
+                    let line = highlight_matches(line, highlighter, &|text| text.normal());
                     wln!(writer, "{:indent$}{}", "", line, indent = BASE_INDENT_SIZE);
                 }
             }
             Value::Object(_) => {
                 write_res_status_code(writer, res_map.remove("statusCode"), None);
-                write_headers(writer, headers);
+                write_headers(writer, headers, highlighter);
             }
             _ => (),
         }
@@ -696,6 +880,7 @@ fn write_res<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
         if !body.is_empty() {
             wln!(writer);
             for line in body.lines() {
+                let line = highlight_matches(line, highlighter, &|text| text.normal());
                 wln!(writer, "{:indent$}{}", "", line, indent = BASE_INDENT_SIZE);
             }
         }
@@ -709,8 +894,9 @@ fn write_res<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
 /// * `writer` - Write implementation to output data to
 /// * `caller_name` - text indicating if we have been invoked from a "req" or "client_req" code path
 /// * `headers` - Mutable map containing header(s) keys. Keys will be removed as processed.
+/// * `highlighter` - Optional regular expression matching text to emphasize
 ///
-fn write_headers<W: Write>(writer: &mut W, headers: &Value) {
+fn write_headers<W: Write>(writer: &mut W, headers: &Value, highlighter: Option<&Regex>) {
     match headers {
         Value::String(headers_string) => {
             for line in headers_string.lines() {
@@ -718,6 +904,7 @@ fn write_headers<W: Write>(writer: &mut W, headers: &Value) {
                     continue;
                 }
 
+                let line = highlight_matches(line, highlighter, &|text| text.normal());
                 wln!(writer, "{:indent$}{}", "", line, indent = BASE_INDENT_SIZE);
             }
         }
@@ -728,6 +915,7 @@ fn write_headers<W: Write>(writer: &mut W, headers: &Value) {
                 let mut is_first = true;
 
                 for line in string_or_value!(v).lines() {
+                    let line = highlight_matches(line, highlighter, &|text| text.normal());
                     if is_first {
                         wln!(writer, " {}", line);
                         is_first = false;
@@ -747,8 +935,13 @@ fn write_headers<W: Write>(writer: &mut W, headers: &Value) {
 ///
 /// * `writer` - Write implementation to output data to
 /// * `other` - Map containing all non-explicitly deserialized keys and values
+/// * `highlighter` - Optional regular expression matching text to emphasize
 ///
-fn write_err<W: Write>(writer: &mut W, other: &mut Map<String, Value>) {
+fn write_err<W: Write>(
+    writer: &mut W,
+    other: &mut Map<String, Value>,
+    highlighter: Option<&Regex>,
+) {
     let err_option = other.remove("err");
 
     if err_option.is_none() {
@@ -767,6 +960,7 @@ fn write_err<W: Write>(writer: &mut W, other: &mut Map<String, Value>) {
         match stack_val {
             Value::String(stack_str) => {
                 for line in stack_str.lines() {
+                    let line = highlight_matches(line, highlighter, &|text| text.normal());
                     wln!(writer, "{:indent$}{}", "", line, indent = BASE_INDENT_SIZE);
                 }
             }
@@ -776,7 +970,8 @@ fn write_err<W: Write>(writer: &mut W, other: &mut Map<String, Value>) {
                         writer,
                         "{:indent$}{}",
                         "",
-                        string_or_value!(line),
+                        highlight_matches(&string_or_value!(line), highlighter, &|text| text
+                            .normal()),
                         indent = BASE_INDENT_SIZE
                     );
                 }
@@ -979,32 +1174,103 @@ fn validate_log_data_structure(line: &BunyanLine) -> Option<BunyanLogParseError>
     None
 }
 
+/// Sections of a log record as they are written by the long format, for formats that lay
+/// them out differently (eg markdown). Sections are empty when the record has nothing to
+/// write in them.
+pub(crate) struct RecordSections {
+    /// Source reference and extra parameters following the message, eg ` (req_id=1)`
+    pub extras: String,
+    /// Requests followed by their responses
+    pub http: Vec<String>,
+    /// Stack trace of the error
+    pub stack: String,
+    /// Multiline messages and values that don't fit in the extra parameters
+    pub details: Vec<String>,
+}
+
+/// Returns the sections of the passed log record as they are written by the long format
+/// with the indentation of the multiline sections removed.
+///
+/// # Arguments
+///
+/// * `line` - log line to split into sections
+///
+/// # Errors
+///
+/// If the JSON data is not in the expected format, a `BunyanLogParseError` is returned.
+///
+pub(crate) fn record_sections(line: &BunyanLine) -> Result<RecordSections, BunyanLogParseError> {
+    /// Returns the text written by the passed function with the base indentation removed.
+    fn capture(write: &mut dyn FnMut(&mut Vec<u8>)) -> String {
+        let mut buffer = Vec::new();
+        write(&mut buffer);
+
+        let indent = " ".repeat(BASE_INDENT_SIZE);
+        String::from_utf8_lossy(&buffer)
+            .lines()
+            .map(|line| line.strip_prefix(indent.as_str()).unwrap_or(line))
+            .collect::<Vec<&str>>()
+            .join("\n")
+    }
+
+    if let Some(err) = validate_log_data_structure(line) {
+        return Err(err);
+    }
+
+    let other = &mut line.other.clone();
+    let mut details: Vec<String> = Vec::new();
+
+    if line.msg.contains('\n') {
+        details.push(line.msg.clone());
+    }
+
+    let extras = capture(&mut |writer| {
+        write_src(writer, other, None);
+        write_all_extra_params(writer, other, &mut details, None);
+    });
+
+    let http = vec![
+        capture(&mut |writer| write_req(writer, "req", other, None)),
+        capture(&mut |writer| write_req(writer, "client_req", other, None)),
+        capture(&mut |writer| write_res(writer, "res", other, None)),
+        capture(&mut |writer| write_res(writer, "client_res", other, None)),
+    ]
+    .into_iter()
+    .filter(|section| !section.is_empty())
+    .collect();
+
+    let stack = capture(&mut |writer| write_err(writer, other, None));
+
+    Ok(RecordSections {
+        extras,
+        http,
+        stack,
+        details,
+    })
+}
+
 impl Logger for BunyanLine {
     fn write_long_format<W: Write>(
         &self,
         writer: &mut W,
-        _output_config: &LoggerOutputConfig,
+        output_config: &LoggerOutputConfig,
     ) -> ParseResult {
-        fn colorize_log_level(level: LogLevel) -> String {
-            match level {
-                LogLevel::TRACE => level.to_string(),
-                LogLevel::DEBUG => level.to_string().yellow().to_string(),
-                LogLevel::INFO => level.to_string().cyan().to_string(),
-                LogLevel::WARN => level.to_string().magenta().to_string(),
-                LogLevel::ERROR => level.to_string().red().to_string(),
-                LogLevel::FATAL => level.to_string().reverse().to_string(),
-                LogLevel::OTHER(_code) => level.to_string(),
-            }
-        }
-
         if let Some(err) = validate_log_data_structure(self) {
             return Err(err);
         }
 
         let log_level: LogLevel = self.level.into();
 
+        let (msg_highlighter, value_highlighter) = match &output_config.grep_filter {
+            Some(grep_filter) => (
+                grep_filter.msg_highlighter(),
+                grep_filter.value_highlighter(),
+            ),
+            None => (None, None),
+        };
+
         // Write the [time]
-        let time = if _output_config.display_local_time {
+        let time = if output_config.display_local_time {
             self.time
                 .with_timezone(&Local)
                 .to_rfc3339_opts(SecondsFormat::Millis, true)
@@ -1016,40 +1282,56 @@ impl Logger for BunyanLine {
             writer,
             "{}{}{}",
             "[".blue(),
-            time.bright_white(),
+            highlight_matches(&time, value_highlighter, &|text| text.bright_white()),
             "]".blue()
         );
 
         // write the log [level] and app [name]
-        w!(writer, " {}: {}/", colorize_log_level(log_level), self.name);
+        w!(
+            writer,
+            " {}: {}/",
+            level_color(&log_level, log_level.to_string().normal()),
+            highlight_matches(&self.name, value_highlighter, &|text| text.normal())
+        );
 
         // If present, write the [component]
         if let Some(ref component) = self.component {
-            w!(writer, "{}/", component);
+            w!(
+                writer,
+                "{}/",
+                highlight_matches(component, value_highlighter, &|text| text.normal())
+            );
         }
 
         // Write the [pid] and [hostname]
-        w!(writer, "{} on {}", self.pid, self.hostname);
+        w!(
+            writer,
+            "{} on {}",
+            self.pid,
+            highlight_matches(&self.hostname, value_highlighter, &|text| text.normal())
+        );
 
         let other = &mut self.other.clone();
 
         // If present, write the source line reference [src]
-        write_src(writer, other);
+        write_src(writer, other, value_highlighter);
 
         let mut details: Vec<String> = Vec::new();
 
         // If our log message [msg] contains a line break, we display it in the details section
         if self.msg.contains('\n') {
-            let indented_msg = format!("{:indent$}{}", "", self.msg, indent = BASE_INDENT_SIZE);
+            let msg = highlight_matches(&self.msg, msg_highlighter, &|text| text.normal());
+            let indented_msg = format!("{:indent$}{}", "", msg, indent = BASE_INDENT_SIZE);
             details.push(indented_msg)
         // Write the log message [msg] as is because there is no line break
         } else if !self.msg.is_empty() {
-            w!(writer, ": {}", self.msg.cyan());
+            let msg = highlight_matches(&self.msg, msg_highlighter, &|text| text.cyan());
+            w!(writer, ": {}", msg);
         } else {
             w!(writer, ":");
         }
 
-        write_all_extra_params(writer, other, &mut details);
+        write_all_extra_params(writer, other, &mut details, value_highlighter);
 
         // Write line feed finishing the first line
         wln!(writer);
@@ -1057,35 +1339,35 @@ impl Logger for BunyanLine {
         let wrapped_writer = &mut DividerWriter::new(writer, true);
 
         // If present, write the request [req]
-        write_req(wrapped_writer, "req", other);
+        write_req(wrapped_writer, "req", other, value_highlighter);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
         }
 
         // If present, write the client request [client_req]
-        write_req(wrapped_writer, "client_req", other);
+        write_req(wrapped_writer, "client_req", other, value_highlighter);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
         }
 
         // If present, write the response [res]
-        write_res(wrapped_writer, "res", other);
+        write_res(wrapped_writer, "res", other, value_highlighter);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
         }
 
         // If present, write the response [client_res]
-        write_res(wrapped_writer, "client_res", other);
+        write_res(wrapped_writer, "client_res", other, value_highlighter);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
         }
 
         // If present, write the error information [err]
-        write_err(wrapped_writer, other);
+        write_err(wrapped_writer, other, value_highlighter);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
@@ -1100,59 +1382,69 @@ impl Logger for BunyanLine {
     fn write_short_format<W: Write>(
         &self,
         writer: &mut W,
-        _output_config: &LoggerOutputConfig,
+        output_config: &LoggerOutputConfig,
     ) -> ParseResult {
-        pub fn right_align_and_colorize_log_level(level: LogLevel) -> String {
-            match level {
-                LogLevel::TRACE => format!("{level: >5}"),
-                LogLevel::DEBUG => format!("{level: >5}").yellow().to_string(),
-                LogLevel::INFO => format!("{level: >5}").cyan().to_string(),
-                LogLevel::WARN => format!("{level: >5}").magenta().to_string(),
-                LogLevel::ERROR => format!("{level: >5}").red().to_string(),
-                LogLevel::FATAL => format!("{level: >5}").reverse().to_string(),
-                LogLevel::OTHER(_code) => level.to_string(),
-            }
-        }
-
         if let Some(err) = validate_log_data_structure(self) {
             return Err(err);
         }
 
         let log_level: LogLevel = self.level.into();
 
+        let (msg_highlighter, value_highlighter) = match &output_config.grep_filter {
+            Some(grep_filter) => (
+                grep_filter.msg_highlighter(),
+                grep_filter.value_highlighter(),
+            ),
+            None => (None, None),
+        };
+
         // Write the [time]
-        let time = if _output_config.display_local_time {
+        let time = if output_config.display_local_time {
             self.time.with_timezone(&Local).format("%H:%M:%S%.3f")
         } else {
             self.time.format("%H:%M:%S%.3fZ")
         }
         .to_string();
 
-        w!(writer, "{}", time.bright_white());
+        w!(
+            writer,
+            "{}",
+            highlight_matches(&time, value_highlighter, &|text| text.bright_white())
+        );
 
         // write the log [level] and app [name]
-        let level_right_indented = right_align_and_colorize_log_level(log_level);
-        w!(writer, " {} {}", level_right_indented, self.name);
+        let level_right_indented = match log_level {
+            LogLevel::OTHER(_code) => log_level.to_string().normal(),
+            _ => level_color(&log_level, format!("{log_level: >5}").normal()),
+        };
+        w!(
+            writer,
+            " {} {}",
+            level_right_indented,
+            highlight_matches(&self.name, value_highlighter, &|text| text.normal())
+        );
 
         let other = &mut self.other.clone();
 
         // If present, write the source line reference [src]
-        write_src(writer, other);
+        write_src(writer, other, value_highlighter);
 
         let mut details: Vec<String> = Vec::new();
 
         // If our log message [msg] contains a line break, we display it in the details section
         if self.msg.contains('\n') {
-            let indented_msg = format!("{:indent$}{}", "", self.msg, indent = BASE_INDENT_SIZE);
+            let msg = highlight_matches(&self.msg, msg_highlighter, &|text| text.normal());
+            let indented_msg = format!("{:indent$}{}", "", msg, indent = BASE_INDENT_SIZE);
             details.push(indented_msg)
         // Write the log message [msg] as is because there is no line break
         } else if !self.msg.is_empty() {
-            w!(writer, ": {}", self.msg.cyan());
+            let msg = highlight_matches(&self.msg, msg_highlighter, &|text| text.cyan());
+            w!(writer, ": {}", msg);
         } else {
             w!(writer, ":");
         }
 
-        write_all_extra_params(writer, other, &mut details);
+        write_all_extra_params(writer, other, &mut details, value_highlighter);
 
         // Write line feed finishing the first line
         wln!(writer);
@@ -1160,35 +1452,35 @@ impl Logger for BunyanLine {
         let wrapped_writer = &mut DividerWriter::new(writer, true);
 
         // If present, write the request [req]
-        write_req(wrapped_writer, "req", other);
+        write_req(wrapped_writer, "req", other, value_highlighter);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
         }
 
         // If present, write the client request [client_req]
-        write_req(wrapped_writer, "client_req", other);
+        write_req(wrapped_writer, "client_req", other, value_highlighter);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
         }
 
         // If present, write the response [res]
-        write_res(wrapped_writer, "res", other);
+        write_res(wrapped_writer, "res", other, value_highlighter);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
         }
 
         // If present, write the response [client_res]
-        write_res(wrapped_writer, "client_res", other);
+        write_res(wrapped_writer, "client_res", other, value_highlighter);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
         }
 
         // If present, write the error information [err]
-        write_err(wrapped_writer, other);
+        write_err(wrapped_writer, other, value_highlighter);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
@@ -0,0 +1,118 @@
+use std::io::Write;
+
+use serde_json::map::Map;
+use serde_json::{json, Value};
+
+use crate::logfmt_logger::flatten;
+use crate::syslog_logger::syslog_severity;
+use crate::BunyanLine;
+
+/// Writes a log record as a single line GELF 1.1 message for Graylog. The message is used as
+/// the short message, the stack of the error (if any) as the full message and the syslog
+/// severity of the level as the level. The name, pid, component and remaining fields are
+/// written as additional fields prefixed with `_`, with nested objects flattened into dotted
+/// names.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `log` - Log record to write
+///
+pub fn write_gelf_line<W: Write>(writer: &mut W, log: &BunyanLine) {
+    let mut message = Map::new();
+    message.insert("version".to_string(), json!("1.1"));
+    message.insert("host".to_string(), json!(log.hostname));
+    message.insert(
+        "short_message".to_string(),
+        json!(if log.msg.is_empty() { "-" } else { &log.msg }),
+    );
+
+    let stack = log
+        .other
+        .get("err")
+        .and_then(|err| err.get("stack"))
+        .and_then(Value::as_str);
+    if let Some(stack) = stack {
+        message.insert("full_message".to_string(), json!(stack));
+    }
+
+    message.insert(
+        "timestamp".to_string(),
+        json!(log.time.timestamp_millis() as f64 / 1000.0),
+    );
+    message.insert("level".to_string(), json!(syslog_severity(log.level)));
+
+    message.insert("_name".to_string(), json!(log.name));
+    message.insert("_pid".to_string(), json!(log.pid));
+    if let Some(component) = &log.component {
+        message.insert("_component".to_string(), json!(component));
+    }
+
+    let mut fields = Vec::new();
+    flatten(&log.other, "", &mut fields);
+
+    for (key, value) in fields {
+        // The stack has already been written as the full message
+        if key == "err.stack" && stack.is_some() {
+            continue;
+        }
+
+        if let Some(value) = additional_field_value(value) {
+            message.insert(additional_field_name(&key), value);
+        }
+    }
+
+    wln!(writer, "{}", Value::Object(message));
+}
+
+/// Returns the name of an additional field, which is prefixed with `_` and may only contain
+/// letters, numbers, underscores, dashes and dots. Other characters are replaced with `_`.
+/// As `_id` is reserved by Graylog, a field named `id` is written as `__id`.
+fn additional_field_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    if name == "id" {
+        "__id".to_string()
+    } else {
+        format!("_{name}")
+    }
+}
+
+/// Returns the value of an additional field, which must be a string or a number. Booleans,
+/// arrays and empty objects are written as strings and null values are left out.
+fn additional_field_value(value: &Value) -> Option<Value> {
+    match value {
+        Value::Null => None,
+        Value::Number(_) | Value::String(_) => Some(value.clone()),
+        _ => Some(Value::String(value.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_name_additional_fields() {
+        assert_eq!(additional_field_name("req.url"), "_req.url");
+        assert_eq!(additional_field_name("x user"), "_x_user");
+        assert_eq!(additional_field_name("id"), "__id");
+    }
+
+    #[test]
+    fn can_convert_additional_field_values() {
+        assert_eq!(additional_field_value(&json!(3)), Some(json!(3)));
+        assert_eq!(additional_field_value(&json!(true)), Some(json!("true")));
+        assert_eq!(additional_field_value(&json!([1, 2])), Some(json!("[1,2]")));
+        assert_eq!(additional_field_value(&Value::Null), None);
+    }
+}
@@ -0,0 +1,145 @@
+use regex::{Regex, RegexBuilder};
+use serde_json::map::Map;
+use serde_json::Value;
+
+/// Filters log records by searching for a regular expression in the message [msg] and
+/// optionally in all of the other string values of a record.
+///
+#[derive(Debug, Clone)]
+pub struct GrepFilter {
+    regex: Regex,
+    search_all_values: bool,
+    invert_match: bool,
+}
+
+impl GrepFilter {
+    /// Creates a new filter from the passed pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - Regular expression to search for
+    /// * `search_all_values` - When true, all string values are searched rather than only `msg`
+    /// * `ignore_case` - When true, the pattern is matched case insensitively
+    /// * `invert_match` - When true, only records that do not match are selected
+    ///
+    /// # Errors
+    ///
+    /// If the pattern is not a valid regular expression, a `regex::Error` is returned.
+    ///
+    pub fn new(
+        pattern: &str,
+        search_all_values: bool,
+        ignore_case: bool,
+        invert_match: bool,
+    ) -> Result<Self, regex::Error> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(ignore_case)
+            .build()?;
+
+        Ok(Self {
+            regex,
+            search_all_values,
+            invert_match,
+        })
+    }
+
+    /// Returns true if the passed record should be displayed.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - Map containing the JSON data of the log record
+    ///
+    pub fn filter(&self, record: &Map<String, Value>) -> bool {
+        let is_match = if self.search_all_values {
+            record.values().any(|value| self.value_matches(value))
+        } else {
+            record
+                .get("msg")
+                .and_then(Value::as_str)
+                .is_some_and(|msg| self.regex.is_match(msg))
+        };
+
+        is_match != self.invert_match
+    }
+
+    /// Returns the regular expression to use for highlighting matched text within the
+    /// message [msg]. When matches are inverted, there is nothing to highlight and `None`
+    /// is returned.
+    ///
+    pub fn msg_highlighter(&self) -> Option<&Regex> {
+        if self.invert_match {
+            None
+        } else {
+            Some(&self.regex)
+        }
+    }
+
+    /// Returns the regular expression to use for highlighting matched text within values
+    /// other than the message [msg]. This is only present when all values are searched.
+    ///
+    pub fn value_highlighter(&self) -> Option<&Regex> {
+        if self.search_all_values {
+            self.msg_highlighter()
+        } else {
+            None
+        }
+    }
+
+    fn value_matches(&self, value: &Value) -> bool {
+        match value {
+            Value::String(text) => self.regex.is_match(text),
+            Value::Array(array) => array.iter().any(|item| self.value_matches(item)),
+            Value::Object(map) => map.values().any(|item| self.value_matches(item)),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> Map<String, Value> {
+        serde_json::from_str(
+            r#"{"msg": "Connection refused", "req": {"url": "/health", "headers": ["X-Trace"]}}"#,
+        )
+        .expect("invalid test JSON")
+    }
+
+    #[test]
+    fn can_match_msg() {
+        assert!(GrepFilter::new("refused", false, false, false)
+            .unwrap()
+            .filter(&record()));
+        assert!(!GrepFilter::new("health", false, false, false)
+            .unwrap()
+            .filter(&record()));
+    }
+
+    #[test]
+    fn can_match_all_values() {
+        assert!(GrepFilter::new("health", true, false, false)
+            .unwrap()
+            .filter(&record()));
+        assert!(GrepFilter::new("X-Trace", true, false, false)
+            .unwrap()
+            .filter(&record()));
+    }
+
+    #[test]
+    fn can_match_ignoring_case() {
+        assert!(!GrepFilter::new("CONNECTION", false, false, false)
+            .unwrap()
+            .filter(&record()));
+        assert!(GrepFilter::new("CONNECTION", false, true, false)
+            .unwrap()
+            .filter(&record()));
+    }
+
+    #[test]
+    fn can_invert_match() {
+        let filter = GrepFilter::new("refused", false, false, true).unwrap();
+        assert!(!filter.filter(&record()));
+        assert!(filter.msg_highlighter().is_none());
+    }
+}
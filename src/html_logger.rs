@@ -0,0 +1,301 @@
+use std::io::Write;
+
+use crate::errors::ParseResult;
+use crate::{BunyanLine, Logger, LoggerOutputConfig};
+
+/// Start of the report up to the element containing the records. Styles are inlined so
+/// that the report can be viewed without network access.
+const HTML_PROLOGUE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Bunyan log report</title>
+<style>
+body { margin: 0; background: #1e1e1e; color: #dcdfe4; font: 13px/1.4 Menlo, Consolas, monospace; }
+#controls { position: sticky; top: 0; display: flex; gap: 8px; align-items: center; padding: 8px; background: #2b2b2b; border-bottom: 1px solid #444; }
+#controls input { flex: 1; }
+#count { color: #9da5b4; }
+#records { padding: 4px 0; }
+.record { padding: 1px 8px; border-left: 4px solid transparent; white-space: pre-wrap; word-break: break-all; }
+.record pre { margin: 0; font: inherit; white-space: pre-wrap; }
+.record summary { cursor: pointer; }
+.record details pre { padding: 4px 0 4px 16px; }
+.level-10 { border-left-color: #5c6370; }
+.level-20 { border-left-color: #e5c07b; }
+.level-30 { border-left-color: #56b6c2; }
+.level-40 { border-left-color: #c678dd; }
+.level-50 { border-left-color: #e06c75; }
+.level-60 { border-left-color: #dcdfe4; background: #3b2020; }
+.raw, .note { color: #9da5b4; }
+.sgr-1 { font-weight: bold; }
+.sgr-2 { opacity: 0.6; }
+.sgr-3 { font-style: italic; }
+.sgr-4 { text-decoration: underline; }
+.sgr-7 { background: #dcdfe4; color: #1e1e1e; }
+.sgr-30 { color: #1e1e1e; }
+.sgr-31 { color: #e06c75; }
+.sgr-32 { color: #98c379; }
+.sgr-33 { color: #e5c07b; }
+.sgr-34 { color: #61afef; }
+.sgr-35 { color: #c678dd; }
+.sgr-36 { color: #56b6c2; }
+.sgr-37 { color: #dcdfe4; }
+.sgr-43 { background: #e5c07b; }
+.sgr-90 { color: #7f848e; }
+.sgr-91 { color: #ff7b86; }
+.sgr-92 { color: #b5e890; }
+.sgr-93 { color: #ffd68a; }
+.sgr-94 { color: #8cc8ff; }
+.sgr-95 { color: #e099ff; }
+.sgr-96 { color: #7fdce6; }
+.sgr-97 { color: #ffffff; }
+</style>
+</head>
+<body>
+<div id="controls">
+<label>Level <select id="level">
+<option value="0">all</option>
+<option value="10">trace</option>
+<option value="20">debug</option>
+<option value="30">info</option>
+<option value="40">warn</option>
+<option value="50">error</option>
+<option value="60">fatal</option>
+</select></label>
+<input id="search" type="search" placeholder="Search">
+<button id="expand" type="button">Expand all</button>
+<button id="collapse" type="button">Collapse all</button>
+<span id="count"></span>
+</div>
+<div id="records">
+"#;
+
+/// End of the report including the script filtering the records by level and text.
+const HTML_EPILOGUE: &str = r#"</div>
+<script>
+(function () {
+  var records = document.querySelectorAll('#records > div');
+  var level = document.getElementById('level');
+  var search = document.getElementById('search');
+  var count = document.getElementById('count');
+
+  function update() {
+    var min = parseInt(level.value, 10);
+    var text = search.value.toLowerCase();
+    var shown = 0;
+
+    for (var i = 0; i < records.length; i++) {
+      var record = records[i];
+      var recordLevel = record.getAttribute('data-level');
+      var visible = (recordLevel === null || parseInt(recordLevel, 10) >= min) &&
+        (text === '' || record.textContent.toLowerCase().indexOf(text) !== -1);
+
+      record.style.display = visible ? '' : 'none';
+      if (visible) {
+        shown++;
+      }
+    }
+
+    count.textContent = shown + ' of ' + records.length + ' lines';
+  }
+
+  function setOpen(open) {
+    var details = document.querySelectorAll('#records details');
+    for (var i = 0; i < details.length; i++) {
+      details[i].open = open;
+    }
+  }
+
+  level.addEventListener('change', update);
+  search.addEventListener('input', update);
+  document.getElementById('expand').addEventListener('click', function () { setOpen(true); });
+  document.getElementById('collapse').addEventListener('click', function () { setOpen(false); });
+  update();
+})();
+</script>
+</body>
+</html>
+"#;
+
+/// Writes the start of a self-contained HTML report, including its styles and controls.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+///
+pub fn write_html_header<W: Write>(writer: &mut W) {
+    w!(writer, "{}", HTML_PROLOGUE);
+}
+
+/// Writes the end of a self-contained HTML report, including the script that filters the
+/// records by level and text.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+///
+pub fn write_html_footer<W: Write>(writer: &mut W) {
+    w!(writer, "{}", HTML_EPILOGUE);
+}
+
+/// Writes a log record as an element of the HTML report. The record is rendered as it is
+/// by the long format, with the first line always visible and the remaining lines (eg the
+/// req, res and err sections) in an expandable element. Colors are carried over from the
+/// long format.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `log` - Log record to write
+/// * `output_config` - Configuration containing the time zone to display and highlighting
+///
+/// # Errors
+///
+/// If the record can't be written in the long format, the error is returned and nothing
+/// is written.
+///
+pub fn write_html_line<W: Write>(
+    writer: &mut W,
+    log: &BunyanLine,
+    output_config: &LoggerOutputConfig,
+) -> ParseResult {
+    let mut long = Vec::new();
+    log.write_long_format(&mut long, output_config)?;
+    let long = String::from_utf8_lossy(&long);
+
+    let (summary, details) = match long.split_once('\n') {
+        Some((summary, details)) => (summary, details.trim_end()),
+        None => (long.trim_end(), ""),
+    };
+
+    w!(
+        writer,
+        "<div class=\"record level-{}\" data-level=\"{}\">",
+        log.level,
+        log.level
+    );
+
+    if details.is_empty() {
+        w!(writer, "{}", ansi_to_html(summary));
+    } else {
+        w!(
+            writer,
+            "<details><summary>{}</summary><pre>{}</pre></details>",
+            ansi_to_html(summary),
+            ansi_to_html(details)
+        );
+    }
+
+    wln!(writer, "</div>");
+
+    Ok(())
+}
+
+/// Writes a line that isn't a log record, such as a line that couldn't be parsed or a
+/// summary of collapsed records, as an element of the HTML report. These lines are shown
+/// regardless of the level selected.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `text` - Text of the line
+/// * `class` - CSS class of the element (`raw` or `note`)
+///
+pub(crate) fn write_html_text<W: Write>(writer: &mut W, text: &str, class: &str) {
+    wln!(
+        writer,
+        "<div class=\"record {}\"><pre>{}</pre></div>",
+        class,
+        ansi_to_html(text)
+    );
+}
+
+/// Returns the text escaped for HTML with its ANSI color sequences converted into spans
+/// with `sgr-N` classes. A reset sequence closes every span that is open.
+fn ansi_to_html(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    let mut open_spans = 0;
+    let mut rest = text;
+
+    while let Some(start) = rest.find("\x1b[") {
+        let params_len = rest[start + 2..]
+            .find(|c: char| !(c.is_ascii_digit() || c == ';'))
+            .unwrap_or(rest.len() - start - 2);
+        let end = start + 2 + params_len;
+
+        // Anything other than a complete color sequence is written without the escape
+        if !rest[end..].starts_with('m') {
+            html.push_str(&escape_html(&rest[..start + 1]));
+            rest = &rest[start + 1..];
+            continue;
+        }
+
+        html.push_str(&escape_html(&rest[..start]));
+
+        let classes: Vec<String> = rest[start + 2..end]
+            .split(';')
+            .filter(|code| !code.is_empty() && *code != "0")
+            .map(|code| format!("sgr-{code}"))
+            .collect();
+
+        if classes.is_empty() {
+            html.push_str(&"</span>".repeat(open_spans));
+            open_spans = 0;
+        } else {
+            html.push_str(&format!("<span class=\"{}\">", classes.join(" ")));
+            open_spans += 1;
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    html.push_str(&escape_html(rest));
+    html.push_str(&"</span>".repeat(open_spans));
+
+    html
+}
+
+/// Returns the text with the characters that have a special meaning in HTML escaped.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c if c.is_control() && c != '\n' && c != '\t' => (),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_escape_html() {
+        assert_eq!(
+            escape_html("<a href=\"x\">Tom & Jerry's</a>"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn can_convert_ansi_colors_to_spans() {
+        assert_eq!(
+            ansi_to_html("\x1b[36mINFO\x1b[0m: \x1b[30;43m<b>\x1b[0m done"),
+            "<span class=\"sgr-36\">INFO</span>: <span class=\"sgr-30 sgr-43\">&lt;b&gt;</span> done"
+        );
+        assert_eq!(ansi_to_html("\x1b[2Jplain"), "[2Jplain");
+        assert_eq!(
+            ansi_to_html("\x1b[1m\x1b[31munclosed"),
+            "<span class=\"sgr-1\"><span class=\"sgr-31\">unclosed</span></span>"
+        );
+    }
+}
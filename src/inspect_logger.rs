@@ -16,7 +16,7 @@ const BASE_INDENT_SIZE: usize = 2;
 /// * `writer` - Write implementation to output data to
 /// * `map` - Mutable map containing JSON data.
 ///
-pub fn write_inspect_line<W: Write>(writer: &mut W, map: Map<String, Value>)
+pub fn write_inspect_line<W>(writer: &mut W, map: Map<String, Value>)
 where
     W: Write,
 {
@@ -47,7 +47,7 @@ where
 /// * `value` - Entry to write
 /// * `indent` - number of spaces to indent
 ///
-fn write_value<W: Write>(writer: &mut W, value: Value, indent: usize)
+fn write_value<W>(writer: &mut W, value: Value, indent: usize)
 where
     W: Write,
 {
@@ -76,7 +76,7 @@ where
 /// * `array` - array to write
 /// * `indent` - number of spaces to indent
 ///
-fn write_array<W: Write>(writer: &mut W, array: Vec<Value>, indent: usize)
+fn write_array<W>(writer: &mut W, array: Vec<Value>, indent: usize)
 where
     W: Write,
 {
@@ -109,7 +109,7 @@ where
 /// * `obj` - object to write
 /// * `indent` - number of spaces to indent
 ///
-fn write_object<W: Write>(writer: &mut W, obj: Map<String, Value>, indent: usize)
+fn write_object<W>(writer: &mut W, obj: Map<String, Value>, indent: usize)
 where
     W: Write,
 {
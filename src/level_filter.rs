@@ -0,0 +1,202 @@
+use crate::errors::LogLevelParseError;
+use crate::LogLevel;
+
+/// Filters log records by level. A minimum level can be set for all records and overridden
+/// for specific loggers by `name` or `component`. Additionally, records can be limited to a
+/// maximum level or to an exact set of levels.
+///
+#[derive(Debug, Clone, Default)]
+pub struct LevelFilter {
+    minimum: Option<u16>,
+    overrides: Vec<(String, u16)>,
+    maximum: Option<u16>,
+    only: Option<Vec<u16>>,
+}
+
+impl LevelFilter {
+    /// Parses a list of minimum levels in the form of `info,db=debug,http.client=warn`. An
+    /// entry without a key applies to all records that do not match a keyed entry. A keyed
+    /// entry applies to records whose `component` or `name` is equal to the key or is a dot
+    /// separated child of the key (eg `http` applies to `http.client`).
+    ///
+    /// # Errors
+    ///
+    /// If any of the levels can't be parsed, a `LogLevelParseError` is returned.
+    ///
+    pub fn parse<S: AsRef<str>>(spec: S) -> Result<LevelFilter, LogLevelParseError> {
+        let mut filter = LevelFilter::default();
+
+        for entry in spec.as_ref().split(',').map(str::trim) {
+            if entry.is_empty() {
+                continue;
+            }
+
+            match entry.split_once('=') {
+                Some((key, level)) => {
+                    let key = key.trim();
+                    if key.is_empty() {
+                        return Err(LogLevelParseError::from(entry.to_string()));
+                    }
+                    filter
+                        .overrides
+                        .push((key.to_string(), LogLevel::parse(level.trim())?.as_u16()));
+                }
+                None => filter.minimum = Some(LogLevel::parse(entry)?.as_u16()),
+            }
+        }
+
+        Ok(filter)
+    }
+
+    /// Sets the maximum level of records to display.
+    ///
+    /// # Errors
+    ///
+    /// If the level can't be parsed, a `LogLevelParseError` is returned.
+    ///
+    pub fn with_maximum<S: Into<String>>(
+        mut self,
+        level: S,
+    ) -> Result<LevelFilter, LogLevelParseError> {
+        self.maximum = Some(LogLevel::parse(level)?.as_u16());
+        Ok(self)
+    }
+
+    /// Limits the records displayed to the passed comma separated list of levels.
+    ///
+    /// # Errors
+    ///
+    /// If any of the levels can't be parsed, a `LogLevelParseError` is returned.
+    ///
+    pub fn with_only<S: AsRef<str>>(
+        mut self,
+        levels: S,
+    ) -> Result<LevelFilter, LogLevelParseError> {
+        let only = levels
+            .as_ref()
+            .split(',')
+            .map(str::trim)
+            .filter(|level| !level.is_empty())
+            .map(|level| LogLevel::parse(level).map(|level| level.as_u16()))
+            .collect::<Result<Vec<u16>, LogLevelParseError>>()?;
+
+        self.only = Some(only);
+        Ok(self)
+    }
+
+    /// Returns true if a record with the passed level, name and component should be displayed.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - Numeric level of the record
+    /// * `name` - Name of the logger that wrote the record
+    /// * `component` - Optional component of the logger that wrote the record
+    ///
+    pub fn is_match(&self, level: u16, name: &str, component: Option<&str>) -> bool {
+        /// Returns true if the value is the key or is a dot separated child of the key.
+        fn key_applies(key: &str, value: &str) -> bool {
+            value
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        }
+
+        let minimum = self
+            .overrides
+            .iter()
+            .filter(|(key, _)| {
+                component.is_some_and(|component| key_applies(key, component))
+                    || key_applies(key, name)
+            })
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, level)| *level)
+            .or(self.minimum);
+
+        minimum.is_none_or(|minimum| level >= minimum)
+            && self.maximum.is_none_or(|maximum| level <= maximum)
+            && self.only.as_ref().is_none_or(|only| only.contains(&level))
+    }
+
+    /// Returns true if a record with the passed level could be displayed by any logger.
+    /// This is used to skip records by level without knowing their logger names.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - Numeric level of the record
+    ///
+    pub fn could_match_level(&self, level: u16) -> bool {
+        // Records of loggers without an override have no minimum when none is set
+        let lowest_minimum = self.minimum.map(|minimum| {
+            self.overrides
+                .iter()
+                .map(|(_, level)| *level)
+                .fold(minimum, u16::min)
+        });
+
+        lowest_minimum.is_none_or(|minimum| level >= minimum)
+            && self.maximum.is_none_or(|maximum| level <= maximum)
+            && self.only.as_ref().is_none_or(|only| only.contains(&level))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_filter_by_minimum_level() {
+        let filter = LevelFilter::parse("warn").unwrap();
+        assert!(filter.is_match(40, "app", None));
+        assert!(filter.is_match(50, "app", None));
+        assert!(!filter.is_match(30, "app", None));
+    }
+
+    #[test]
+    fn can_override_level_by_name_or_component() {
+        let filter = LevelFilter::parse("info,db=debug,http=error,http.client=warn").unwrap();
+        assert!(filter.is_match(20, "app", Some("db")));
+        assert!(!filter.is_match(20, "app", None));
+        assert!(filter.is_match(20, "db", None));
+        assert!(!filter.is_match(40, "app", Some("http")));
+        assert!(!filter.is_match(40, "app", Some("http.server")));
+        assert!(filter.is_match(40, "app", Some("http.client")));
+        assert!(!filter.is_match(30, "app", Some("http.client.pool")));
+        assert!(filter.is_match(30, "app", Some("httpd")));
+    }
+
+    #[test]
+    fn can_filter_by_level_range() {
+        let filter = LevelFilter::parse("debug")
+            .unwrap()
+            .with_maximum("warn")
+            .unwrap();
+        assert!(!filter.is_match(10, "app", None));
+        assert!(filter.is_match(20, "app", None));
+        assert!(filter.is_match(40, "app", None));
+        assert!(!filter.is_match(50, "app", None));
+    }
+
+    #[test]
+    fn can_filter_by_exact_levels() {
+        let filter = LevelFilter::default().with_only("error,fatal").unwrap();
+        assert!(!filter.is_match(40, "app", None));
+        assert!(filter.is_match(50, "app", None));
+        assert!(filter.is_match(60, "app", None));
+    }
+
+    #[test]
+    fn can_match_level_of_any_logger() {
+        let filter = LevelFilter::parse("warn,db=debug").unwrap();
+        assert!(filter.could_match_level(20));
+        assert!(!filter.could_match_level(10));
+        assert!(LevelFilter::parse("db=error")
+            .unwrap()
+            .could_match_level(10));
+    }
+
+    #[test]
+    fn invalid_levels_are_errors() {
+        assert!(LevelFilter::parse("info,db=loud").is_err());
+        assert!(LevelFilter::parse("=debug").is_err());
+        assert!(LevelFilter::default().with_maximum("loud").is_err());
+    }
+}
@@ -2,31 +2,94 @@
 extern crate serde_derive;
 extern crate chrono;
 extern crate colored;
+extern crate flate2;
 extern crate httpstatus;
+#[cfg(target_os = "linux")]
+extern crate inotify;
 extern crate json_pretty;
 extern crate serde;
 extern crate serde_json;
 
 #[macro_use]
 mod macros;
+mod combined_logger;
 mod condition_filter;
+mod context_buffer;
 mod date_deserializer;
+mod delimited_logger;
+mod dir_watcher;
 mod divider_writer;
+mod ecs_logger;
 mod errors;
+mod field_filter;
+mod field_projection;
 mod formatting_logger;
+mod gelf_logger;
+mod grep_filter;
+mod html_logger;
 mod inspect_logger;
+mod level_filter;
+mod log_index;
+mod logfmt_logger;
+mod markdown_logger;
+mod otlp_logger;
+mod record_throttle;
+mod record_transformer;
+mod repeat_collapser;
+mod request_tracer;
+mod reverse_lines;
+mod rotated_files;
+mod state_file;
+mod syslog_logger;
+mod template_logger;
+mod time_range;
 
+use crate::combined_logger::write_combined_line;
+use crate::context_buffer::{window_start, ContextBuffer, ContextOutput};
+use crate::delimited_logger::{write_delimited_header, write_delimited_line, Delimiter};
+use crate::ecs_logger::write_ecs_line;
 use crate::errors::LogLevelParseError;
+use crate::gelf_logger::write_gelf_line;
+use crate::html_logger::{write_html_footer, write_html_header, write_html_line, write_html_text};
 use crate::inspect_logger::write_inspect_line;
+use crate::log_index::has_indexed_filter;
+use crate::logfmt_logger::write_logfmt_line;
+use crate::markdown_logger::write_markdown_line;
+use crate::otlp_logger::write_otlp_line;
+use crate::record_throttle::RecordThrottle;
+use crate::repeat_collapser::{RepeatCollapser, RepeatKey};
+use crate::reverse_lines::ReverseLines;
+use crate::syslog_logger::write_syslog_line;
 
 pub use crate::condition_filter::ConditionFilter;
+pub use crate::context_buffer::{parse_duration, ContextConfig};
+pub use crate::delimited_logger::DEFAULT_COLUMNS;
+pub use crate::dir_watcher::{DirWatcher, WatchConfig};
+pub use crate::field_filter::FieldFilter;
+pub use crate::field_projection::FieldProjection;
+pub use crate::grep_filter::GrepFilter;
+pub use crate::level_filter::LevelFilter;
+pub use crate::log_index::LogIndex;
+pub use crate::record_throttle::{RateLimitConfig, SampleConfig};
+pub use crate::record_transformer::RecordTransformer;
+pub use crate::repeat_collapser::CollapseConfig;
+pub use crate::request_tracer::RequestTracer;
+pub use crate::rotated_files::rotated_family;
+pub use crate::state_file::{ReadSegment, ReadState};
+pub use crate::template_logger::{Template, DEFAULT_TEMPLATE};
+pub use crate::time_range::TimeRange;
 
 use std::borrow::Cow;
+use std::collections::VecDeque;
 use std::fmt;
-use std::io::{BufRead, Write};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
 
 use crate::errors::{Error, Kind, ParseResult};
 use chrono::prelude::*;
+use colored::*;
+use flate2::read::GzDecoder;
 use serde_json::map::Map;
 use serde_json::Error as SerdeError;
 use serde_json::Value;
@@ -37,6 +100,10 @@ use json_pretty::PrettyFormatter;
 const BASE_INDENT_SIZE: usize = 4;
 /// Minimum fields needed to be a valid bunyan log line
 const REQUIRED_FIELDS: [&str; 6] = ["v", "level", "hostname", "pid", "time", "msg"];
+/// Separator written between groups of context records that are not adjacent
+const CONTEXT_SEPARATOR: &str = "--";
+/// Marker written around the summary preceding the records of each traced request
+const TRACE_MARKER: &str = "===";
 
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Hash)]
 pub enum LogLevel {
@@ -171,6 +238,27 @@ pub enum LogFormat {
     Long,
     Short,
     Simple,
+    Template(Template),
+    Logfmt,
+    /// Comma separated values with the given columns
+    Csv(Vec<String>),
+    /// Tab separated values with the given columns
+    Tsv(Vec<String>),
+    /// Self-contained HTML report
+    Html,
+    /// Markdown tables and fenced code blocks
+    Markdown,
+    /// OpenTelemetry logs in the OTLP/JSON encoding
+    OtlpJson,
+    /// Graylog Extended Log Format 1.1 messages
+    Gelf,
+    /// RFC 5424 syslog lines
+    Syslog,
+    /// Elastic Common Schema documents, optionally preceded by `_bulk` actions creating
+    /// them in the given index
+    Ecs(Option<String>),
+    /// Apache/NCSA combined access log lines of the records with a request and response
+    Combined,
 }
 
 impl LogFormat {
@@ -181,8 +269,44 @@ impl LogFormat {
             LogFormat::Long => "long".into(),
             LogFormat::Short => "short".into(),
             LogFormat::Simple => "simple".into(),
+            LogFormat::Template(_) => "template".into(),
+            LogFormat::Logfmt => "logfmt".into(),
+            LogFormat::Csv(_) => "csv".into(),
+            LogFormat::Tsv(_) => "tsv".into(),
+            LogFormat::Html => "html".into(),
+            LogFormat::Markdown => "markdown".into(),
+            LogFormat::OtlpJson => "otlp-json".into(),
+            LogFormat::Gelf => "gelf".into(),
+            LogFormat::Syslog => "syslog".into(),
+            LogFormat::Ecs(_) => "ecs".into(),
+            LogFormat::Combined => "combined".into(),
         }
     }
+
+    /// Returns true if records are written in a format meant to be parsed by other tools
+    /// (eg NDJSON or logfmt), in which case anything else (eg summaries and separators) is
+    /// written to stderr so that the output can still be parsed.
+    pub fn is_structured(&self) -> bool {
+        matches!(
+            self,
+            LogFormat::Json(_)
+                | LogFormat::Logfmt
+                | LogFormat::Csv(_)
+                | LogFormat::Tsv(_)
+                | LogFormat::OtlpJson
+                | LogFormat::Gelf
+                | LogFormat::Syslog
+                | LogFormat::Ecs(_)
+                | LogFormat::Combined
+        )
+    }
+
+    /// Returns true if records are converted into another structured format, in which case
+    /// lines that aren't records (eg plain text) are written to stderr and blank lines are
+    /// dropped. JSON output passes such lines through as node-bunyan does.
+    pub fn is_conversion(&self) -> bool {
+        self.is_structured() && !matches!(self, LogFormat::Json(_))
+    }
 }
 
 pub trait LogWriter {
@@ -205,6 +329,44 @@ impl LogWriter for LogFormat {
             LogFormat::Long => log.write_long_format(writer, output_config),
             LogFormat::Short => log.write_short_format(writer, output_config),
             LogFormat::Simple => log.write_simple_format(writer, output_config),
+            LogFormat::Template(ref template) => {
+                template.write(writer, &log, output_config);
+                Ok(())
+            }
+            LogFormat::Logfmt => {
+                write_logfmt_line(writer, &log, output_config);
+                Ok(())
+            }
+            LogFormat::Csv(ref columns) => {
+                write_delimited_line(writer, &log, columns, Delimiter::Comma, output_config);
+                Ok(())
+            }
+            LogFormat::Tsv(ref columns) => {
+                write_delimited_line(writer, &log, columns, Delimiter::Tab, output_config);
+                Ok(())
+            }
+            LogFormat::Html => write_html_line(writer, &log, output_config),
+            LogFormat::Markdown => write_markdown_line(writer, &log, output_config),
+            LogFormat::OtlpJson => {
+                write_otlp_line(writer, &log);
+                Ok(())
+            }
+            LogFormat::Gelf => {
+                write_gelf_line(writer, &log);
+                Ok(())
+            }
+            LogFormat::Syslog => {
+                write_syslog_line(writer, &log, output_config);
+                Ok(())
+            }
+            LogFormat::Ecs(ref bulk_index) => {
+                write_ecs_line(writer, &log, bulk_index.as_deref());
+                Ok(())
+            }
+            LogFormat::Combined => {
+                write_combined_line(writer, &log, output_config);
+                Ok(())
+            }
             _ => panic!("Invalid format"),
         }
     }
@@ -215,12 +377,52 @@ pub struct LoggerOutputConfig {
     pub indent: usize,
     pub is_strict: bool,
     pub is_debug: bool,
-    pub level: Option<u16>,
+    pub level: Option<LevelFilter>,
+    pub time_range: Option<TimeRange>,
     pub condition_filter: Option<ConditionFilter>,
+    pub field_filter: Option<FieldFilter>,
+    pub grep_filter: Option<GrepFilter>,
+    pub record_transformer: Option<RecordTransformer>,
+    pub projection: Option<FieldProjection>,
+    pub context: Option<ContextConfig>,
+    pub collapse: Option<CollapseConfig>,
+    pub sample: Option<SampleConfig>,
+    pub rate_limit: Option<RateLimitConfig>,
+    pub head: Option<usize>,
+    pub tail: Option<usize>,
+    /// Create and use the sidecar index of uncompressed log files to skip the parts that
+    /// can't match the time, level and name filters
+    pub use_index: bool,
     pub display_local_time: bool,
     pub format: LogFormat,
 }
 
+impl Default for LoggerOutputConfig {
+    fn default() -> Self {
+        Self {
+            indent: BASE_INDENT_SIZE,
+            is_strict: false,
+            is_debug: false,
+            level: None,
+            time_range: None,
+            condition_filter: None,
+            field_filter: None,
+            grep_filter: None,
+            record_transformer: None,
+            projection: None,
+            context: None,
+            collapse: None,
+            sample: None,
+            rate_limit: None,
+            head: None,
+            tail: None,
+            use_index: false,
+            display_local_time: false,
+            format: LogFormat::Long,
+        }
+    }
+}
+
 fn handle_error<W>(writer: &mut W, error: &Error, output_config: &LoggerOutputConfig)
 where
     W: Write,
@@ -250,7 +452,13 @@ where
         }
 
         if !output_config.is_strict {
-            wln!(writer, "{}", error.line());
+            if output_config.format == LogFormat::Html {
+                write_html_text(writer, error.line(), "raw");
+            } else if output_config.format.is_conversion() {
+                wln!(std::io::stderr(), "{}", error.line());
+            } else {
+                wln!(writer, "{}", error.line());
+            }
         }
     }
 }
@@ -278,95 +486,1057 @@ fn write_zero_indent_json<W>(
     }
 }
 
+/// Returns the passed line with the transformations applied if it contains a JSON object.
+/// Otherwise, the line is returned as is with leading whitespace removed.
+///
+/// # Arguments
+///
+/// * `record_transformer` - Transformations to apply to the record
+/// * `line` - Raw line of input
+///
+fn transform_line(record_transformer: &RecordTransformer, line: &str) -> String {
+    let trimmed = line.trim_start();
+
+    serde_json::from_str::<Map<String, Value>>(trimmed)
+        .ok()
+        .and_then(|record| record_transformer.transform(&record))
+        .and_then(|transformed| serde_json::to_string(&transformed).ok())
+        .unwrap_or_else(|| trimmed.to_string())
+}
+
+/// Returns the passed line with only the projected fields if it contains a JSON object.
+/// Otherwise, the line is returned as is.
+///
+/// # Arguments
+///
+/// * `projection` - Fields to keep and remove
+/// * `line` - Line of input with leading whitespace removed
+///
+fn project_line(projection: &FieldProjection, line: String) -> String {
+    serde_json::from_str::<Map<String, Value>>(&line)
+        .ok()
+        .and_then(|record| serde_json::to_string(&projection.project(&record)).ok())
+        .unwrap_or(line)
+}
+
+/// Outcome of passing a line of input through the filtering stage
+enum FilterOutcome {
+    /// The line was not inspected because no filtering is configured or it isn't a log
+    /// record with a level and time (eg plain text), so it is passed to the formatter as is
+    Unfiltered,
+    /// The line is a log record written at the given time that satisfied all filters
+    Matched(DateTime<Utc>),
+    /// The line is a log record written at the given time that was rejected by a filter
+    Rejected(DateTime<Utc>),
+}
+
+/// Returns true if any filter that selects records is configured.
+fn has_record_filters(output_config: &LoggerOutputConfig) -> bool {
+    output_config.level.is_some()
+        || output_config.time_range.is_some()
+        || output_config.field_filter.is_some()
+        || output_config.grep_filter.is_some()
+        || output_config.condition_filter.is_some()
+}
+
+/// Applies the level, time, field, grep and condition filters to the passed line followed by
+/// any sampling or rate limiting. This is done once for each line before it reaches a
+/// formatter, so that the same records are selected regardless of the output format.
+/// Summaries of the records suppressed by sampling or rate limiting are returned alongside
+/// the outcome.
+///
+/// # Arguments
+///
+/// * `line` - Line of input with leading whitespace removed
+/// * `output_config` - Configuration containing the filters to apply
+/// * `throttle` - Optional throttle sampling and rate limiting the matched records
+///
+fn filter_line(
+    line: &str,
+    output_config: &LoggerOutputConfig,
+    throttle: Option<&mut RecordThrottle>,
+) -> (FilterOutcome, Vec<String>) {
+    if !has_record_filters(output_config)
+        && output_config.context.is_none()
+        && output_config.head.is_none()
+        && throttle.is_none()
+    {
+        return (FilterOutcome::Unfiltered, Vec::new());
+    }
+
+    let record = match serde_json::from_str::<Map<String, Value>>(line) {
+        Ok(record) => record,
+        Err(_) => return (FilterOutcome::Unfiltered, Vec::new()),
+    };
+
+    match (filter_record(&record, output_config), throttle) {
+        (FilterOutcome::Matched(time), Some(throttle)) => {
+            let level = record_level(&record).unwrap_or_default();
+            let (admitted, summaries) = throttle.admit(&record, level, time);
+            if admitted {
+                (FilterOutcome::Matched(time), summaries)
+            } else {
+                (FilterOutcome::Rejected(time), summaries)
+            }
+        }
+        (outcome, _) => (outcome, Vec::new()),
+    }
+}
+
+/// Returns the numeric level of the passed record if it is present and valid.
+fn record_level(record: &Map<String, Value>) -> Option<u16> {
+    record
+        .get("level")
+        .and_then(Value::as_u64)
+        .and_then(|level| u16::try_from(level).ok())
+}
+
+/// Applies the level, time, field, grep and condition filters to the passed record.
+///
+/// # Arguments
+///
+/// * `record` - Map containing the JSON data of the log record
+/// * `output_config` - Configuration containing the filters to apply
+///
+fn filter_record(record: &Map<String, Value>, output_config: &LoggerOutputConfig) -> FilterOutcome {
+    let level = record_level(record);
+    let time = record
+        .get("time")
+        .and_then(Value::as_str)
+        .and_then(|time| date_deserializer::parse_timestamp(time).ok());
+
+    let (level, time) = match (level, time) {
+        (Some(level), Some(time)) => (level, time),
+        _ => return FilterOutcome::Unfiltered,
+    };
+
+    let name = record.get("name").and_then(Value::as_str).unwrap_or("");
+    let component = record.get("component").and_then(Value::as_str);
+
+    let is_match = output_config
+        .level
+        .as_ref()
+        .is_none_or(|level_filter| level_filter.is_match(level, name, component))
+        && output_config
+            .time_range
+            .as_ref()
+            .is_none_or(|time_range| time_range.contains(time))
+        && output_config
+            .field_filter
+            .as_ref()
+            .is_none_or(|field_filter| field_filter.filter(record))
+        && output_config
+            .grep_filter
+            .as_ref()
+            .is_none_or(|grep_filter| grep_filter.filter(record))
+        && output_config
+            .condition_filter
+            .as_ref()
+            .is_none_or(|condition_filter| condition_filter.filter(record));
+
+    if is_match {
+        FilterOutcome::Matched(time)
+    } else {
+        FilterOutcome::Rejected(time)
+    }
+}
+
+/// Writes the records selected by a `ContextBuffer`. Separators between non-adjacent
+/// groups of records are written to stderr for structured output so that it can still be
+/// parsed.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `output` - Records selected by the context buffer along with their line numbers
+/// * `output_config` - Configuration containing the output format
+///
+fn write_context_output<W: Write>(
+    writer: &mut W,
+    output: ContextOutput<(String, usize)>,
+    collapser: &mut Option<RepeatCollapser>,
+    output_config: &LoggerOutputConfig,
+) {
+    if output.separator {
+        write_note(writer, CONTEXT_SEPARATOR.normal(), output_config);
+    }
+
+    for (line, line_no) in output.items {
+        write_record(writer, line, line_no, collapser, output_config);
+    }
+}
+
+/// Writes summaries of records that were collapsed, sampled or rate limited.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `summaries` - Summary lines to write
+/// * `output_config` - Configuration containing the output format
+///
+fn write_summaries<W: Write>(
+    writer: &mut W,
+    summaries: Vec<String>,
+    output_config: &LoggerOutputConfig,
+) {
+    for summary in summaries {
+        write_note(writer, summary.dimmed(), output_config);
+    }
+}
+
+/// Writes a line describing the output rather than a record, such as a summary or a
+/// separator. For structured output, the line is written to stderr without its styling so
+/// that the output can still be parsed.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `note` - Line to write
+/// * `output_config` - Configuration containing the output format
+///
+fn write_note<W: Write>(writer: &mut W, note: ColoredString, output_config: &LoggerOutputConfig) {
+    if output_config.format.is_structured() {
+        wln!(std::io::stderr(), "{}", &*note);
+    } else if output_config.format == LogFormat::Html {
+        write_html_text(writer, &note.to_string(), "note");
+    } else {
+        wln!(writer, "{}", note);
+    }
+}
+
+/// Writes a line of input that has passed the filters unless it is a repeat of a previous
+/// record that is being collapsed.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `line` - Line of input with leading whitespace removed
+/// * `line_no` - Line number of the input
+/// * `collapser` - Optional collapser tracking repeated records
+/// * `output_config` - Configuration containing the output format
+///
+fn write_record<W: Write>(
+    writer: &mut W,
+    line: String,
+    line_no: usize,
+    collapser: &mut Option<RepeatCollapser>,
+    output_config: &LoggerOutputConfig,
+) {
+    if let Some(collapser) = collapser.as_mut() {
+        if let Ok(log) = serde_json::from_str::<BunyanLine>(&line) {
+            let key = RepeatKey::new(log.level, &log.name, &log.msg);
+            let (write, summaries) = collapser.push(key, &log.msg, log.time);
+            write_summaries(writer, summaries, output_config);
+
+            if !write {
+                return;
+            }
+        }
+    }
+
+    write_line(writer, line, line_no, output_config);
+}
+
+/// Writes a single line of input in the configured output format and handles any errors
+/// encountered when parsing or formatting it.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `line` - Line of input with leading whitespace removed
+/// * `line_no` - Line number of the input
+/// * `output_config` - Configuration containing the output format
+///
+fn write_line<W: Write>(
+    writer: &mut W,
+    line: String,
+    line_no: usize,
+    output_config: &LoggerOutputConfig,
+) {
+    let format = &output_config.format;
+
+    if let LogFormat::Json(indent) = format {
+        let line = match &output_config.projection {
+            Some(projection) => project_line(projection, line),
+            None => line,
+        };
+
+        // single line JSON format
+        if *indent < 1 {
+            write_zero_indent_json(writer, line, output_config, line_no);
+        // multi-line indented JSON format with custom indentation
+        } else {
+            let formatter = PrettyFormatter::from_str(&line).indent(*indent);
+            wln!(writer, "{}", formatter.pretty());
+        }
+    // Inspect log format
+    } else if LogFormat::Inspect == *format {
+        let json_result: Result<Map<String, Value>, SerdeError> = serde_json::from_str(&line);
+
+        match json_result {
+            Ok(map) => {
+                let has_missing_fields = REQUIRED_FIELDS
+                    .iter()
+                    .any(|field| !map.contains_key(*field));
+                // Write JSON-0 output if there are missing fields
+                if has_missing_fields {
+                    write_zero_indent_json(writer, line, output_config, line_no);
+                } else {
+                    write_inspect_line(writer, map);
+                }
+            }
+            Err(raw_error) => {
+                let column: usize = raw_error.column();
+                let kind = Kind::from(raw_error);
+                let error = Error::new(kind, line, line_no, Some(column));
+                handle_error(writer, &error, output_config);
+            }
+        }
+    // Custom log format (eg long, short, simple)
+    } else {
+        let json_result: Result<BunyanLine, SerdeError> = serde_json::from_str(&line);
+        match json_result {
+            Ok(log) => {
+                let result = format.write_log(writer, log, output_config);
+                if let Err(e) = result {
+                    let kind = Kind::from(e);
+                    let error = Error::new(kind, line, line_no, None);
+                    handle_error(writer, &error, output_config);
+                }
+            }
+            Err(raw_error) => {
+                let column: usize = raw_error.column();
+                let kind = Kind::from(raw_error);
+                let error = Error::new(kind, line, line_no, Some(column));
+                handle_error(writer, &error, output_config);
+            }
+        }
+    }
+}
+
+/// Returns the outcome of filtering a raw line of input without sampling or rate limiting
+/// it. This is used to find the last records of an input.
+///
+/// # Arguments
+///
+/// * `line` - Raw line of input
+/// * `output_config` - Configuration containing the filters and transformations to apply
+///
+fn classify_line(line: &str, output_config: &LoggerOutputConfig) -> FilterOutcome {
+    let trimmed = match &output_config.record_transformer {
+        Some(record_transformer) => transform_line(record_transformer, line),
+        None => line.trim_start().to_string(),
+    };
+
+    match serde_json::from_str::<Map<String, Value>>(&trimmed) {
+        Ok(record) => filter_record(&record, output_config),
+        Err(_) => FilterOutcome::Unfiltered,
+    }
+}
+
+/// A line of input held while looking for the last records that satisfy the filters
+struct TailLine {
+    line_no: usize,
+    line: String,
+    time: Option<DateTime<Utc>>,
+    is_match: bool,
+}
+
+/// Returns the lines of input starting at the first of the last `count` records that
+/// satisfy the filters. Lines that aren't records (eg plain text) following that record are
+/// kept. Records rejected by the filters are dropped as they won't be displayed, unless
+/// context is configured in which case they are kept so that they can be displayed as the
+/// context of the matching records.
+///
+/// # Arguments
+///
+/// * `lines` - Line numbers and raw lines of input
+/// * `output_config` - Configuration containing the filters and transformations to apply
+/// * `count` - Number of matching records to keep
+///
+fn tail_lines<I>(lines: I, output_config: &LoggerOutputConfig, count: usize) -> Vec<(usize, String)>
+where
+    I: Iterator<Item = (usize, String)>,
+{
+    let mut buffer: VecDeque<TailLine> = VecDeque::new();
+    let mut matched: usize = 0;
+
+    for (line_no, line) in lines {
+        let (time, is_match) = match classify_line(&line, output_config) {
+            FilterOutcome::Matched(time) => (Some(time), true),
+            FilterOutcome::Unfiltered => (None, false),
+            FilterOutcome::Rejected(time) if output_config.context.is_some() => (Some(time), false),
+            FilterOutcome::Rejected(_) => continue,
+        };
+
+        if is_match {
+            matched += 1;
+        }
+        buffer.push_back(TailLine {
+            line_no,
+            line,
+            time,
+            is_match,
+        });
+
+        // Earlier matches are kept as they may be displayed as the context of later ones
+        if matched > count {
+            if let Some(first_match) = buffer.iter_mut().find(|tail_line| tail_line.is_match) {
+                first_match.is_match = false;
+                matched -= 1;
+            }
+        }
+
+        if matched == count {
+            trim_leading_lines(&mut buffer, output_config.context.as_ref());
+        }
+    }
+
+    buffer
+        .into_iter()
+        .map(|tail_line| (tail_line.line_no, tail_line.line))
+        .collect()
+}
+
+/// Drops the lines preceding the first matching record that won't be displayed as its
+/// context.
+fn trim_leading_lines(buffer: &mut VecDeque<TailLine>, context: Option<&ContextConfig>) {
+    let mut leading = match buffer.iter().position(|tail_line| tail_line.is_match) {
+        Some(leading) => leading,
+        None => return,
+    };
+
+    let before = context.map_or(0, |context| context.before);
+    let earliest = context
+        .and_then(|context| context.time_window)
+        .and_then(|window| buffer[leading].time.map(|time| window_start(time, window)));
+
+    while leading > before {
+        let in_window = buffer
+            .front()
+            .and_then(|tail_line| tail_line.time)
+            .zip(earliest)
+            .is_some_and(|(time, earliest)| time >= earliest);
+
+        if in_window {
+            break;
+        }
+
+        buffer.pop_front();
+        leading -= 1;
+    }
+}
+
+/// Returns the byte offset of the first of the last `count` records satisfying the filters
+/// by reading the input backwards from its end. The input can then be read forwards from
+/// the offset in order to display the records without parsing all of the preceding input.
+/// If the input contains fewer matching records, the offset of the start of the input is
+/// returned.
+///
+/// # Arguments
+///
+/// * `reader` - Seekable source of log records such as an uncompressed file
+/// * `output_config` - Configuration containing the filters and transformations to apply
+/// * `count` - Number of matching records to find
+///
+/// # Errors
+///
+/// If the reader can't be seeked or read, an IO error is returned.
+///
+pub fn find_tail_offset<R>(
+    mut reader: R,
+    output_config: &LoggerOutputConfig,
+    count: usize,
+) -> std::io::Result<u64>
+where
+    R: Read + Seek,
+{
+    if count == 0 {
+        return reader.seek(SeekFrom::End(0));
+    }
+
+    let mut matched: usize = 0;
+
+    for line in ReverseLines::new(reader)? {
+        let (offset, line) = line?;
+
+        if let FilterOutcome::Matched(_) = classify_line(&line, output_config) {
+            matched += 1;
+            if matched >= count {
+                return Ok(offset);
+            }
+        }
+    }
+
+    Ok(0)
+}
+
+/// Writes anything that precedes the records of all inputs in the configured output format,
+/// such as the header row of CSV and TSV output or the start of an HTML report. This is
+/// written once regardless of the number of inputs.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `output_config` - Configuration containing the output format
+///
+pub fn write_output_header<W: Write>(writer: &mut W, output_config: &LoggerOutputConfig) {
+    match &output_config.format {
+        LogFormat::Csv(columns) => write_delimited_header(writer, columns, Delimiter::Comma),
+        LogFormat::Tsv(columns) => write_delimited_header(writer, columns, Delimiter::Tab),
+        LogFormat::Html => write_html_header(writer),
+        _ => {}
+    }
+}
+
+/// Writes anything that follows the records of all inputs in the configured output format,
+/// such as the end of an HTML report. This is written once after every input has been read.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `output_config` - Configuration containing the output format
+///
+pub fn write_output_footer<W: Write>(writer: &mut W, output_config: &LoggerOutputConfig) {
+    if output_config.format == LogFormat::Html {
+        write_html_footer(writer);
+    }
+}
+
 pub fn write_bunyan_output<W, R>(writer: &mut W, reader: R, output_config: &LoggerOutputConfig)
 where
     W: Write,
     R: BufRead,
 {
-    let mut line_no: usize = 0;
-    let format = &output_config.format;
+    write_numbered_lines(writer, reader, 1, output_config);
+}
 
-    reader.lines().for_each(|raw_line| {
-        match raw_line {
-            Ok(line) => {
-                line_no += 1;
-                let trimmed = line.trim_start().to_string();
-
-                // Don't process empty lines because the output isn't useful to our users
-                if !output_config.is_strict && trimmed.trim_end().is_empty() {
-                    wln!(writer);
-                } else if let LogFormat::Json(indent) = format {
-                    // single line JSON format
-                    if *indent < 1 {
-                        write_zero_indent_json(writer, trimmed, output_config, line_no);
-                    // multi-line indented JSON format with custom indentation
-                    } else {
-                        let formatter = PrettyFormatter::from_str(&trimmed).indent(*indent);
-                        wln!(writer, "{}", formatter.pretty());
-                    }
-                // Inspect log format
-                } else if LogFormat::Inspect == *format {
-                    let json_result: Result<Map<String, Value>, SerdeError> =
-                        serde_json::from_str(&trimmed);
-
-                    match json_result {
-                        Ok(map) => {
-                            let has_missing_fields = REQUIRED_FIELDS
-                                .iter()
-                                .any(|field| !map.contains_key(*field));
-                            // Write JSON-0 output if there are missing fields
-                            if has_missing_fields {
-                                write_zero_indent_json(writer, trimmed, output_config, line_no);
-                            } else {
-                                write_inspect_line(writer, map);
-                            }
-                        }
-                        Err(raw_error) => {
-                            let column: usize = raw_error.column();
-                            let kind = Kind::from(raw_error);
-                            let error = Error::new(kind, trimmed, line_no, Some(column));
-                            handle_error(writer, &error, output_config);
-                        }
-                    }
-                // Custom log format (eg long, short, simple)
-                } else {
-                    let json_result: Result<BunyanLine, SerdeError> =
-                        serde_json::from_str(&trimmed);
-                    match json_result {
-                        Ok(log) => {
-                            let write_log = match output_config.level {
-                                Some(output_level) => output_level <= log.level,
-                                None => true,
-                            } && match &output_config.condition_filter {
-                                Some(condition_filter) => condition_filter.filter(line.as_str()),
-                                None => true,
-                            };
-
-                            if write_log {
-                                let result = format.write_log(writer, log, output_config);
-                                if let Err(e) = result {
-                                    let kind = Kind::from(e);
-                                    let error = Error::new(kind, trimmed, line_no, None);
-                                    handle_error(writer, &error, output_config);
-                                }
-                            }
-                        }
-                        Err(raw_error) => {
-                            let column: usize = raw_error.column();
-                            let kind = Kind::from(raw_error);
-                            let error = Error::new(kind, trimmed, line_no, Some(column));
-                            handle_error(writer, &error, output_config);
-                        }
-                    }
-                }
+/// Writes the last `count` records of a seekable input that satisfy the filters. The input
+/// is read backwards from its end to find the first of the records, so that the preceding
+/// input doesn't need to be parsed. When context is configured, the whole input is read as
+/// the records preceding the first match may be displayed as its context.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `reader` - Seekable source of log records such as an uncompressed file
+/// * `output_config` - Configuration containing the filters and output format
+/// * `count` - Number of matching records to write
+///
+/// # Errors
+///
+/// If the reader can't be seeked or read, an IO error is returned.
+///
+pub fn write_tail_output<W, R>(
+    writer: &mut W,
+    mut reader: R,
+    output_config: &LoggerOutputConfig,
+    count: usize,
+) -> std::io::Result<()>
+where
+    W: Write,
+    R: BufRead + Seek,
+{
+    let offset = match output_config.context {
+        Some(_) => 0,
+        None => find_tail_offset(&mut reader, output_config, count)?,
+    };
+
+    // Line numbers are only reported by --debug, so the skipped lines are only counted then
+    let first_line = if output_config.is_debug && offset > 0 {
+        reader.seek(SeekFrom::Start(0))?;
+        count_lines((&mut reader).take(offset))? + 1
+    } else {
+        1
+    };
+
+    reader.seek(SeekFrom::Start(offset))?;
+    write_numbered_lines(writer, reader, first_line, output_config);
+
+    Ok(())
+}
+
+/// Returns the number of lines terminated by a line feed in the passed input.
+fn count_lines<R: BufRead>(mut reader: R) -> std::io::Result<usize> {
+    let mut count: usize = 0;
+
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(count);
+        }
+
+        count += buffer.iter().filter(|byte| **byte == b'\n').count();
+        let length = buffer.len();
+        reader.consume(length);
+    }
+}
+
+/// Writes the lines of the passed input numbering them from `first_line`.
+fn write_numbered_lines<W, R>(
+    writer: &mut W,
+    reader: R,
+    first_line: usize,
+    output_config: &LoggerOutputConfig,
+) where
+    W: Write,
+    R: BufRead,
+{
+    let lines = reader
+        .lines()
+        .enumerate()
+        .map(|(index, raw_line)| match raw_line {
+            Ok(line) => (first_line + index, line),
+            Err(e) => {
+                panic!("{}", e);
             }
+        });
+
+    match output_config.tail {
+        Some(count) => write_lines(
+            writer,
+            tail_lines(lines, output_config, count).into_iter(),
+            output_config,
+        ),
+        None => write_lines(writer, lines, output_config),
+    }
+}
+
+/// Writes the records of a log file, which may be compressed with gzip. Uncompressed files
+/// are read in the most efficient way for the configured filters: when the index is enabled
+/// and a time, level or name filter is configured the file's sidecar index is used to skip
+/// the parts of the file that can't match, and when only the last records are wanted the
+/// file is read backwards from its end.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `path` - Path of the log file
+/// * `output_config` - Configuration containing the filters and output format
+///
+/// # Errors
+///
+/// If the log file can't be read, an IO error is returned.
+///
+pub fn write_bunyan_file<W>(
+    writer: &mut W,
+    path: &Path,
+    output_config: &LoggerOutputConfig,
+) -> std::io::Result<()>
+where
+    W: Write,
+{
+    let file = File::open(path)?;
+
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        let reader = BufReader::new(GzDecoder::new(BufReader::new(file)));
+        write_bunyan_output(writer, reader, output_config);
+        Ok(())
+    } else if output_config.use_index && has_indexed_filter(output_config) {
+        write_indexed_file(writer, path, output_config)
+    } else if let Some(count) = output_config.tail {
+        write_tail_output(writer, BufReader::new(file), output_config, count)
+    } else {
+        write_bunyan_output(writer, BufReader::new(file), output_config);
+        Ok(())
+    }
+}
+
+/// Writes the records of a log file using its sidecar index (eg `app.log.bvidx`) to skip
+/// the blocks of lines that can't contain records satisfying the time, level and name
+/// filters. The index is created when it doesn't exist and refreshed when the file has
+/// grown. Lines appended after the index was refreshed are always read. No blocks are
+/// skipped when context or transformations are configured, as these depend on records
+/// that would otherwise be rejected.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `path` - Path of the uncompressed log file
+/// * `output_config` - Configuration containing the filters and output format
+///
+/// # Errors
+///
+/// If the log file can't be read, an IO error is returned.
+///
+fn write_indexed_file<W>(
+    writer: &mut W,
+    path: &Path,
+    output_config: &LoggerOutputConfig,
+) -> std::io::Result<()>
+where
+    W: Write,
+{
+    let index = LogIndex::load_or_build(path, output_config.is_debug)?;
+    let can_skip = output_config.context.is_none() && output_config.record_transformer.is_none();
+
+    // Adjacent blocks that may match are read as a single range
+    let mut ranges: Vec<LineRange> = Vec::new();
+    for block in index.blocks.iter() {
+        if can_skip && !block.may_match(output_config) {
+            continue;
+        }
+
+        match ranges.last_mut() {
+            Some(range) if range.end == Some(block.offset) => range.end = Some(block.end),
+            _ => ranges.push(LineRange {
+                start: block.offset,
+                end: Some(block.end),
+                first_line: block.first_line,
+            }),
+        }
+    }
+
+    // Lines written after the index was refreshed
+    match ranges.last_mut() {
+        Some(range) if range.end == Some(index.indexed_size()) => range.end = None,
+        _ => ranges.push(LineRange {
+            start: index.indexed_size(),
+            end: None,
+            first_line: index.indexed_lines() + 1,
+        }),
+    }
+
+    let lines = RangeLines {
+        reader: BufReader::new(File::open(path)?),
+        ranges: ranges.into(),
+        remaining: Some(0),
+        line_no: 0,
+    };
+
+    match output_config.tail {
+        Some(count) => write_lines(
+            writer,
+            tail_lines(lines, output_config, count).into_iter(),
+            output_config,
+        ),
+        None => write_lines(writer, lines, output_config),
+    }
+
+    Ok(())
+}
+
+/// A range of lines of a file to read
+struct LineRange {
+    /// Byte offset of the first line of the range
+    start: u64,
+    /// Byte offset following the last line of the range, or None to read to the end
+    end: Option<u64>,
+    /// Line number of the first line of the range
+    first_line: usize,
+}
+
+/// Iterator over the numbered lines of ranges of a file, which are read through a single
+/// handle by seeking from the end of one range to the start of the next.
+struct RangeLines<R> {
+    reader: BufReader<R>,
+    ranges: VecDeque<LineRange>,
+    /// Number of bytes left to read in the current range, or None to read to the end
+    remaining: Option<u64>,
+    line_no: usize,
+}
+
+impl<R: Read + Seek> Iterator for RangeLines<R> {
+    type Item = (usize, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Move to the start of the next range once the current one has been read
+        while self.remaining == Some(0) {
+            let range = self.ranges.pop_front()?;
+            if let Err(e) = self.reader.seek(SeekFrom::Start(range.start)) {
+                panic!("{}", e);
+            }
+            self.remaining = range.end.map(|end| end - range.start);
+            self.line_no = range.first_line;
+        }
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let read = match self.reader.read_until(b'\n', &mut buffer) {
+            Ok(read) => read,
             Err(e) => {
                 panic!("{}", e);
             }
+        };
+
+        if read == 0 {
+            self.remaining = Some(0);
+            return self.next();
+        }
+
+        self.remaining = self
+            .remaining
+            .map(|remaining| remaining.saturating_sub(read as u64));
+        let line_no = self.line_no;
+        self.line_no += 1;
+
+        if buffer.ends_with(b"\n") {
+            buffer.pop();
+            if buffer.ends_with(b"\r") {
+                buffer.pop();
+            }
+        }
+
+        Some((line_no, String::from_utf8_lossy(&buffer).into_owned()))
+    }
+}
+
+/// Writes the passed lines of input in the configured output format after passing them
+/// through the filtering stage.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `lines` - Line numbers and raw lines of input
+/// * `output_config` - Configuration containing the filters and output format
+///
+fn write_lines<W, I>(writer: &mut W, lines: I, output_config: &LoggerOutputConfig)
+where
+    W: Write,
+    I: Iterator<Item = (usize, String)>,
+{
+    let mut pipeline = RecordPipeline::new(output_config);
+
+    for (line_no, line) in lines {
+        // Stop reading once enough matching records and their context have been written
+        if pipeline.is_done(output_config) {
+            break;
+        }
+
+        pipeline.write_line(writer, line_no, line, output_config);
+    }
+
+    pipeline.finish(writer, output_config);
+}
+
+/// State carried from one line of input to the next as lines pass through the filtering
+/// stage, such as the records buffered as context and the repeats being collapsed. Holding
+/// it outside of `write_lines` allows input that arrives in batches (eg when following
+/// files) to be filtered as a single stream.
+///
+pub(crate) struct RecordPipeline {
+    matched: usize,
+    context_buffer: Option<ContextBuffer<(String, usize)>>,
+    collapser: Option<RepeatCollapser>,
+    throttle: Option<RecordThrottle>,
+}
+
+impl RecordPipeline {
+    pub(crate) fn new(output_config: &LoggerOutputConfig) -> Self {
+        let throttle = if output_config.sample.is_some() || output_config.rate_limit.is_some() {
+            Some(RecordThrottle::new(
+                output_config.sample.clone(),
+                output_config.rate_limit.clone(),
+            ))
+        } else {
+            None
+        };
+
+        Self {
+            matched: 0,
+            context_buffer: output_config
+                .context
+                .as_ref()
+                .map(|context| ContextBuffer::new(context.clone())),
+            collapser: output_config
+                .collapse
+                .as_ref()
+                .map(|collapse| RepeatCollapser::new(collapse.clone())),
+            throttle,
+        }
+    }
+
+    /// Returns true once `--head` matching records have been written along with any
+    /// context following the last of them, so no further input needs to be read.
+    pub(crate) fn is_done(&self, output_config: &LoggerOutputConfig) -> bool {
+        self.is_head_reached(output_config)
+            && !self
+                .context_buffer
+                .as_ref()
+                .is_some_and(ContextBuffer::has_trailing_context)
+    }
+
+    fn is_head_reached(&self, output_config: &LoggerOutputConfig) -> bool {
+        output_config.head.is_some_and(|head| self.matched >= head)
+    }
+
+    /// Passes a single line of input through the filtering stage and writes it if it's
+    /// selected.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Write implementation to output data to
+    /// * `line_no` - Line number of the input
+    /// * `line` - Raw line of input
+    /// * `output_config` - Configuration containing the filters and output format
+    ///
+    pub(crate) fn write_line<W: Write>(
+        &mut self,
+        writer: &mut W,
+        line_no: usize,
+        line: String,
+        output_config: &LoggerOutputConfig,
+    ) {
+        if self.is_done(output_config) {
+            return;
+        }
+
+        let trimmed = match &output_config.record_transformer {
+            Some(record_transformer) => transform_line(record_transformer, &line),
+            None => line.trim_start().to_string(),
+        };
+
+        // Don't process empty lines because the output isn't useful to our users
+        if !output_config.is_strict && trimmed.trim_end().is_empty() {
+            if !output_config.format.is_conversion() {
+                wln!(writer);
+            }
+            return;
+        }
+
+        // Only the context following the last match is written once enough records matched
+        if self.is_head_reached(output_config) {
+            let (outcome, _) = filter_line(&trimmed, output_config, None);
+            let context_buffer = self
+                .context_buffer
+                .as_mut()
+                .expect("trailing context requires a context buffer");
+
+            let trailing = match outcome {
+                FilterOutcome::Unfiltered => Some((trimmed, line_no)),
+                FilterOutcome::Matched(time) | FilterOutcome::Rejected(time) => {
+                    context_buffer.push_trailing(time, (trimmed, line_no))
+                }
+            };
+
+            if let Some((line, line_no)) = trailing {
+                write_record(writer, line, line_no, &mut self.collapser, output_config);
+            }
+            return;
+        }
+
+        let (outcome, summaries) = filter_line(&trimmed, output_config, self.throttle.as_mut());
+        write_summaries(writer, summaries, output_config);
+
+        if let FilterOutcome::Matched(_) = outcome {
+            self.matched += 1;
+        }
+
+        match (outcome, self.context_buffer.as_mut()) {
+            (FilterOutcome::Unfiltered, _) | (FilterOutcome::Matched(_), None) => {
+                write_record(writer, trimmed, line_no, &mut self.collapser, output_config);
+            }
+            (FilterOutcome::Rejected(_), None) => {}
+            // Write the matching record along with its surrounding records
+            (FilterOutcome::Matched(time), Some(context_buffer)) => {
+                let output = context_buffer.push_match(time, (trimmed, line_no));
+                write_context_output(writer, output, &mut self.collapser, output_config);
+            }
+            (FilterOutcome::Rejected(time), Some(context_buffer)) => {
+                let output = context_buffer.push_non_match(time, (trimmed, line_no));
+                write_context_output(writer, output, &mut self.collapser, output_config);
+            }
+        }
+    }
+
+    /// Ends the input currently being read before reading another, so that the records of
+    /// different inputs aren't displayed as each other's context or collapsed together. The
+    /// summaries of repeats still being collapsed are written, while the matches counted
+    /// towards `--head` and the state of sampling and rate limiting carry over.
+    pub(crate) fn end_input<W: Write>(
+        &mut self,
+        writer: &mut W,
+        output_config: &LoggerOutputConfig,
+    ) {
+        if let Some(collapser) = self.collapser.as_mut() {
+            write_summaries(writer, collapser.finish(), output_config);
+        }
+        self.context_buffer = output_config
+            .context
+            .as_ref()
+            .map(|context| ContextBuffer::new(context.clone()));
+    }
+
+    /// Writes the summaries of repeats still being collapsed and of records suppressed by
+    /// sampling or rate limiting once the input has ended.
+    pub(crate) fn finish<W: Write>(&mut self, writer: &mut W, output_config: &LoggerOutputConfig) {
+        if let Some(collapser) = self.collapser.as_mut() {
+            write_summaries(writer, collapser.finish(), output_config);
+        }
+        if let Some(throttle) = self.throttle.as_mut() {
+            write_summaries(writer, throttle.finish(), output_config);
+        }
+    }
+}
+
+/// Reads log records from the passed reader and adds those that satisfy the filters and
+/// hold a correlation id to the tracer. Nothing is written until all inputs have been
+/// read and `write_trace_output` is called.
+///
+/// # Arguments
+///
+/// * `reader` - Source of log records
+/// * `output_config` - Configuration containing the filters and transformations to apply
+/// * `tracer` - Tracer collecting the records of requests across all inputs
+///
+pub fn collect_trace_records<R>(
+    reader: R,
+    output_config: &LoggerOutputConfig,
+    tracer: &mut RequestTracer,
+) where
+    R: BufRead,
+{
+    let mut line_no: usize = 0;
+
+    reader.lines().for_each(|raw_line| match raw_line {
+        Ok(line) => {
+            line_no += 1;
+            let trimmed = match &output_config.record_transformer {
+                Some(record_transformer) => transform_line(record_transformer, &line),
+                None => line.trim_start().to_string(),
+            };
+
+            if let Ok(record) = serde_json::from_str::<Map<String, Value>>(&trimmed) {
+                let ids = tracer.correlation_ids(&record);
+                if !ids.is_empty() {
+                    if let FilterOutcome::Matched(time) = filter_record(&record, output_config) {
+                        tracer.push(ids, time, &record, trimmed, line_no);
+                    }
+                }
+            }
+        }
+        Err(e) => {
+            panic!("{}", e);
         }
     });
 }
 
+/// Writes the records collected by the tracer grouped by request and in time order. For
+/// formats other than JSON, each request is preceded by a summary of its method, url,
+/// status code and duration.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `tracer` - Tracer containing the collected records
+/// * `output_config` - Configuration containing the output format
+///
+pub fn write_trace_output<W>(
+    writer: &mut W,
+    tracer: RequestTracer,
+    output_config: &LoggerOutputConfig,
+) where
+    W: Write,
+{
+    for trace in tracer.into_traces() {
+        let summary = format!("{} {} {}", TRACE_MARKER, trace.summary(), TRACE_MARKER);
+        write_note(writer, summary.bold(), output_config);
+
+        for record in trace.records {
+            write_line(writer, record.line, record.line_no, output_config);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -393,6 +1563,56 @@ mod tests {
         assert_log_levels_parse(levels);
     }
 
+    fn context_config(format: LogFormat) -> LoggerOutputConfig {
+        LoggerOutputConfig {
+            level: Some(LevelFilter::parse("error").unwrap()),
+            context: Some(ContextConfig::default()),
+            format,
+            ..Default::default()
+        }
+    }
+
+    fn record(level: u16, msg: &str) -> String {
+        format!(
+            "{{\"name\":\"app\",\"hostname\":\"h\",\"pid\":1,\"level\":{level},\"msg\":\"{msg}\",\"time\":\"2020-01-01T00:00:00Z\",\"v\":0}}\n"
+        )
+    }
+
+    #[test]
+    fn separators_are_kept_out_of_structured_output() {
+        let input = [
+            record(50, "first"),
+            record(30, "skipped"),
+            record(50, "second"),
+        ]
+        .concat();
+
+        let mut writer: Vec<u8> = Vec::new();
+        write_bunyan_output(
+            &mut writer,
+            input.as_bytes(),
+            &context_config(LogFormat::Long),
+        );
+        assert!(String::from_utf8(writer)
+            .unwrap()
+            .contains(CONTEXT_SEPARATOR));
+
+        let columns = vec!["level".to_string(), "msg".to_string()];
+        for format in [
+            LogFormat::Json(0),
+            LogFormat::Logfmt,
+            LogFormat::Csv(columns.clone()),
+            LogFormat::Tsv(columns),
+            LogFormat::Syslog,
+        ] {
+            let mut writer: Vec<u8> = Vec::new();
+            write_bunyan_output(&mut writer, input.as_bytes(), &context_config(format));
+            let output = String::from_utf8(writer).unwrap();
+            assert_eq!(output.lines().count(), 2);
+            assert!(!output.contains(CONTEXT_SEPARATOR));
+        }
+    }
+
     fn assert_log_levels_parse(levels: Vec<LogLevel>) {
         for test_level in levels {
             let level_string = test_level.as_string();
@@ -0,0 +1,436 @@
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Result as IoResult, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde_json::map::Map;
+use serde_json::Value;
+
+use crate::date_deserializer::parse_timestamp;
+use crate::record_throttle::fnv1a_hash;
+use crate::state_file::{complete_length, fingerprint, inode};
+use crate::LoggerOutputConfig;
+
+/// Extension appended to the name of a log file to form the name of its index
+const INDEX_EXTENSION: &str = ".bvidx";
+/// Version of the index format, an index with a different version is rebuilt
+const INDEX_VERSION: u32 = 2;
+/// Number of lines summarized by each block of the index
+const BLOCK_LINES: usize = 1000;
+/// Number of hash functions used by the bloom filter of names
+const BLOOM_HASHES: u64 = 3;
+
+/// Bloom filter of the `name` and `component` values of the records within a block
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NameBloom {
+    bits: [u64; 4],
+}
+
+impl NameBloom {
+    const BIT_COUNT: u64 = 256;
+
+    /// Adds the value of a field to the filter.
+    pub fn insert(&mut self, field: &str, value: &str) {
+        for bit in Self::bit_positions(field, value) {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    /// Returns false if no record within the block has the value for the field. A return
+    /// value of true means that a record may have the value.
+    pub fn might_contain(&self, field: &str, value: &str) -> bool {
+        Self::bit_positions(field, value)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    fn bit_positions(field: &str, value: &str) -> impl Iterator<Item = u64> {
+        let hash = fnv1a_hash(format!("{field}={value}").as_bytes());
+        let (first, second) = (hash & 0xffff_ffff, hash >> 32);
+
+        (0..BLOOM_HASHES)
+            .map(move |i| (first.wrapping_add(i.wrapping_mul(second))) % Self::BIT_COUNT)
+    }
+}
+
+/// Summary of a contiguous range of lines of a log file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexBlock {
+    /// Byte offset of the first line of the block
+    pub offset: u64,
+    /// Byte offset following the last line of the block
+    pub end: u64,
+    /// Line number of the first line of the block
+    pub first_line: usize,
+    /// Earliest and latest timestamps of the records within the block
+    pub min_time: Option<DateTime<Utc>>,
+    pub max_time: Option<DateTime<Utc>>,
+    /// Distinct levels of the records within the block
+    pub levels: Vec<u16>,
+    pub names: NameBloom,
+    /// True if the block contains lines that aren't log records (eg plain text), which
+    /// are always displayed
+    pub has_other_lines: bool,
+}
+
+impl IndexBlock {
+    fn new(offset: u64, first_line: usize) -> Self {
+        Self {
+            offset,
+            end: offset,
+            first_line,
+            min_time: None,
+            max_time: None,
+            levels: Vec::new(),
+            names: NameBloom::default(),
+            has_other_lines: false,
+        }
+    }
+
+    /// Returns false if none of the lines of the block can be displayed with the filters of
+    /// the passed configuration, so that the block can be skipped without being read.
+    /// Blocks containing lines that aren't records are never skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_config` - Configuration containing the filters to apply
+    ///
+    pub fn may_match(&self, output_config: &LoggerOutputConfig) -> bool {
+        if self.has_other_lines {
+            return true;
+        }
+
+        let (min_time, max_time) = match (self.min_time, self.max_time) {
+            (Some(min_time), Some(max_time)) => (min_time, max_time),
+            _ => return false,
+        };
+
+        let is_in_range = output_config
+            .time_range
+            .as_ref()
+            .is_none_or(|time_range| time_range.overlaps(min_time, max_time));
+        let has_level = output_config.level.as_ref().is_none_or(|level_filter| {
+            self.levels
+                .iter()
+                .any(|level| level_filter.could_match_level(*level))
+        });
+        let has_names = output_config
+            .field_filter
+            .as_ref()
+            .is_none_or(|field_filter| {
+                ["name", "component"].iter().all(|field| {
+                    field_filter
+                        .required_value(field)
+                        .is_none_or(|value| self.names.might_contain(field, value))
+                })
+            });
+
+        is_in_range && has_level && has_names
+    }
+
+    /// Adds the summary of a line to the block.
+    fn add_line(&mut self, line: &str) {
+        let record = serde_json::from_str::<Map<String, Value>>(line.trim_start()).ok();
+        let level = record
+            .as_ref()
+            .and_then(|record| record.get("level"))
+            .and_then(Value::as_u64)
+            .and_then(|level| u16::try_from(level).ok());
+        let time = record
+            .as_ref()
+            .and_then(|record| record.get("time"))
+            .and_then(Value::as_str)
+            .and_then(|time| parse_timestamp(time).ok());
+
+        let (record, level, time) = match (record, level, time) {
+            (Some(record), Some(level), Some(time)) => (record, level, time),
+            _ => {
+                self.has_other_lines = true;
+                return;
+            }
+        };
+
+        if !self.levels.contains(&level) {
+            self.levels.push(level);
+        }
+        self.min_time = Some(self.min_time.map_or(time, |min_time| min_time.min(time)));
+        self.max_time = Some(self.max_time.map_or(time, |max_time| max_time.max(time)));
+
+        for field in ["name", "component"] {
+            if let Some(value) = record.get(field).filter(|value| !value.is_null()) {
+                self.names.insert(field, &string_or_value!(value));
+            }
+        }
+    }
+}
+
+/// Returns true if the passed configuration has a filter that blocks of the index can be
+/// skipped by: a time range, a level or a `name` or `component` field filter.
+pub(crate) fn has_indexed_filter(output_config: &LoggerOutputConfig) -> bool {
+    output_config.time_range.is_some()
+        || output_config.level.is_some()
+        || output_config
+            .field_filter
+            .as_ref()
+            .is_some_and(|field_filter| {
+                ["name", "component"]
+                    .iter()
+                    .any(|field| field_filter.required_value(field).is_some())
+            })
+}
+
+/// Sparse index of a log file stored alongside it (eg `app.log.bvidx`). The file is
+/// divided into blocks of lines, each summarized by the range of times, the levels and a
+/// bloom filter of the logger names of its records, so that blocks which can't contain
+/// records satisfying the filters can be skipped without being parsed.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogIndex {
+    version: u32,
+    inode: u64,
+    /// Number of bytes of the log file that have been indexed
+    size: u64,
+    /// Hash of the bytes preceding the end of the indexed data, which no longer matches
+    /// when the log file has been truncated and rewritten past it
+    fingerprint: u64,
+    /// Number of lines of the log file that have been indexed
+    lines: usize,
+    pub blocks: Vec<IndexBlock>,
+}
+
+impl LogIndex {
+    /// Returns the index of the log file, creating it if it doesn't exist and adding the
+    /// lines written since it was last updated. The index file is rebuilt when the log file
+    /// has been replaced or truncated, including when it has since grown past the indexed
+    /// data. Failure to save the index isn't an error as it can still be used for the
+    /// current run, but it is reported when debugging.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the uncompressed log file
+    /// * `is_debug` - True if failure to save the index should be reported on stderr
+    ///
+    /// # Errors
+    ///
+    /// If the log file can't be read, an IO error is returned.
+    ///
+    pub fn load_or_build(path: &Path, is_debug: bool) -> IoResult<LogIndex> {
+        let metadata = fs::metadata(path)?;
+        let inode = inode(&metadata);
+        let size = complete_length(path, metadata.len())?;
+        let index_path = Self::index_path(path);
+
+        let existing = fs::read_to_string(&index_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<LogIndex>(&contents).ok())
+            .filter(|index| {
+                index.version == INDEX_VERSION && index.inode == inode && index.size <= size
+            });
+        let existing = match existing {
+            Some(index) if fingerprint(path, index.size)? == index.fingerprint => Some(index),
+            _ => None,
+        };
+
+        let mut index = match existing {
+            Some(index) => index,
+            None => LogIndex {
+                version: INDEX_VERSION,
+                inode,
+                size: 0,
+                fingerprint: fingerprint(path, 0)?,
+                lines: 0,
+                blocks: Vec::new(),
+            },
+        };
+
+        if index.size < size {
+            index.extend(path, size)?;
+            index.fingerprint = fingerprint(path, size)?;
+
+            if let Err(e) = index.save(&index_path) {
+                if is_debug {
+                    wln!(
+                        std::io::stderr(),
+                        "Unable to save index {}: {}",
+                        index_path.display(),
+                        e
+                    );
+                }
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Writes the index to the passed path.
+    fn save(&self, index_path: &Path) -> IoResult<()> {
+        let contents = serde_json::to_string(self)?;
+        fs::write(index_path, contents)
+    }
+
+    /// Returns the number of bytes of the log file that have been indexed.
+    pub fn indexed_size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the number of lines of the log file that have been indexed.
+    pub fn indexed_lines(&self) -> usize {
+        self.lines
+    }
+
+    /// Returns the path of the index of the passed log file.
+    pub fn index_path(path: &Path) -> PathBuf {
+        let mut index_path = path.as_os_str().to_owned();
+        index_path.push(INDEX_EXTENSION);
+        PathBuf::from(index_path)
+    }
+
+    /// Adds blocks summarizing the lines between the end of the indexed data and `size`.
+    fn extend(&mut self, path: &Path, size: u64) -> IoResult<()> {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(self.size))?;
+        let mut reader = BufReader::new(file.take(size - self.size));
+
+        let mut offset = self.size;
+        let mut block = IndexBlock::new(offset, self.lines + 1);
+        let mut block_lines: usize = 0;
+        let mut buffer: Vec<u8> = Vec::new();
+
+        loop {
+            buffer.clear();
+            let read = reader.read_until(b'\n', &mut buffer)?;
+            if read == 0 {
+                break;
+            }
+
+            block.add_line(&String::from_utf8_lossy(&buffer));
+            offset += read as u64;
+            block.end = offset;
+            block_lines += 1;
+            self.lines += 1;
+
+            if block_lines == BLOCK_LINES {
+                self.blocks.push(block);
+                block = IndexBlock::new(offset, self.lines + 1);
+                block_lines = 0;
+            }
+        }
+
+        if block_lines > 0 {
+            self.blocks.push(block);
+        }
+        self.size = size;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log(name: &str, contents: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("bunyan-index-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn record(level: u16, name: &str, second: usize) -> String {
+        format!(
+            "{{\"name\":\"{name}\",\"pid\":1,\"hostname\":\"h\",\"level\":{level},\"msg\":\"m\",\"time\":\"2020-01-01T00:{:02}:{:02}Z\",\"v\":0}}\n",
+            second / 60,
+            second % 60
+        )
+    }
+
+    #[test]
+    fn bloom_contains_inserted_values() {
+        let mut bloom = NameBloom::default();
+        bloom.insert("name", "api");
+        assert!(bloom.might_contain("name", "api"));
+        assert!(!bloom.might_contain("component", "api"));
+        assert!(!bloom.might_contain("name", "worker"));
+    }
+
+    #[test]
+    fn can_build_and_extend_index() {
+        let contents: String = (0..1500)
+            .map(|i| record(if i < 1000 { 20 } else { 50 }, "api", i))
+            .collect();
+        let path = temp_log("build", &contents);
+
+        let index = LogIndex::load_or_build(&path, false).unwrap();
+        assert_eq!(index.blocks.len(), 2);
+        assert_eq!(index.blocks[0].levels, vec![20]);
+        assert_eq!(index.blocks[1].levels, vec![50]);
+        assert_eq!(index.blocks[1].first_line, 1001);
+        assert!(LogIndex::index_path(&path).exists());
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        std::io::Write::write_all(&mut file, record(30, "worker", 2000).as_bytes()).unwrap();
+
+        let index = LogIndex::load_or_build(&path, false).unwrap();
+        assert_eq!(index.blocks.len(), 3);
+        assert_eq!(index.blocks[2].first_line, 1501);
+        assert!(index.blocks[2].names.might_contain("name", "worker"));
+        assert_eq!(index.blocks[2].end, fs::metadata(&path).unwrap().len());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn can_skip_blocks_not_matching_filters() {
+        let mut block = IndexBlock::new(0, 1);
+        block.add_line(&record(30, "api", 0));
+        block.add_line(&record(40, "api", 59));
+
+        let config = |level: &str, since: &str, name: &str| LoggerOutputConfig {
+            level: Some(crate::LevelFilter::parse(level).unwrap()),
+            time_range: crate::TimeRange::parse(Some(since), None, Utc::now()).ok(),
+            field_filter: crate::FieldFilter::new(vec![format!("name={name}")]).ok(),
+            ..Default::default()
+        };
+
+        assert!(block.may_match(&config("warn", "2020-01-01T00:00:30Z", "api")));
+        assert!(!block.may_match(&config("error", "2020-01-01T00:00:30Z", "api")));
+        assert!(!block.may_match(&config("warn", "2020-01-01T00:01:00Z", "api")));
+        assert!(!block.may_match(&config("warn", "2020-01-01T00:00:30Z", "worker")));
+
+        block.add_line("plain text\n");
+        assert!(block.may_match(&config("error", "2020-01-01T00:00:30Z", "api")));
+    }
+
+    #[test]
+    fn index_is_rebuilt_when_log_is_truncated() {
+        let contents: String = (0..10).map(|i| record(30, "api", i)).collect();
+        let path = temp_log("truncated", &contents);
+        LogIndex::load_or_build(&path, false).unwrap();
+
+        fs::write(&path, "not a record\n").unwrap();
+        let index = LogIndex::load_or_build(&path, false).unwrap();
+        assert_eq!(index.blocks.len(), 1);
+        assert!(index.blocks[0].has_other_lines);
+        assert!(index.blocks[0].levels.is_empty());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn index_is_rebuilt_when_log_is_truncated_and_grows() {
+        let contents: String = (0..10).map(|i| record(30, "api", i)).collect();
+        let path = temp_log("regrown", &contents);
+        LogIndex::load_or_build(&path, false).unwrap();
+
+        let contents: String = (0..20).map(|i| record(50, "worker", i)).collect();
+        fs::write(&path, contents).unwrap();
+        let index = LogIndex::load_or_build(&path, false).unwrap();
+        assert_eq!(index.blocks.len(), 1);
+        assert_eq!(index.blocks[0].levels, vec![50]);
+        assert_eq!(index.indexed_lines(), 20);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+use std::io::Write;
+
+use chrono::{Local, SecondsFormat};
+use serde_json::map::Map;
+use serde_json::Value;
+
+use crate::{BunyanLine, LogLevel, LoggerOutputConfig};
+
+/// Writes a log record as a single logfmt line in the form of
+/// `time=… level=info name=… msg="…" key=value`. Nested objects such as `req` and `err`
+/// are flattened into dotted keys (eg `req.method=GET`), while arrays are written as JSON.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `log` - Log record to write
+/// * `output_config` - Configuration containing the time zone to display
+///
+pub fn write_logfmt_line<W: Write>(
+    writer: &mut W,
+    log: &BunyanLine,
+    output_config: &LoggerOutputConfig,
+) {
+    let time = if output_config.display_local_time {
+        log.time
+            .with_timezone(&Local)
+            .to_rfc3339_opts(SecondsFormat::Millis, true)
+    } else {
+        log.time.to_rfc3339_opts(SecondsFormat::Millis, true)
+    };
+
+    let mut pairs: Vec<(String, String)> = vec![
+        ("time".to_string(), time),
+        (
+            "level".to_string(),
+            LogLevel::from(log.level).as_string().to_ascii_lowercase(),
+        ),
+        ("name".to_string(), log.name.clone()),
+    ];
+    if let Some(component) = &log.component {
+        pairs.push(("component".to_string(), component.clone()));
+    }
+    pairs.push(("msg".to_string(), log.msg.clone()));
+    pairs.push(("hostname".to_string(), log.hostname.clone()));
+    pairs.push(("pid".to_string(), log.pid.to_string()));
+
+    let mut fields = Vec::new();
+    flatten(&log.other, "", &mut fields);
+    pairs.extend(
+        fields
+            .into_iter()
+            .map(|(key, value)| (key, string_or_value!(value))),
+    );
+
+    let line: Vec<String> = pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", format_key(key), format_value(value)))
+        .collect();
+
+    wln!(writer, "{}", line.join(" "));
+}
+
+/// Adds the fields of the object to the key value pairs, prefixing their keys with the
+/// passed prefix and descending into nested objects. Arrays and empty objects are added
+/// as they are.
+pub(crate) fn flatten<'a>(
+    object: &'a Map<String, Value>,
+    prefix: &str,
+    pairs: &mut Vec<(String, &'a Value)>,
+) {
+    for (key, value) in object {
+        let key = format!("{prefix}{key}");
+
+        match value {
+            Value::Object(nested) if !nested.is_empty() => {
+                flatten(nested, &format!("{key}."), pairs);
+            }
+            _ => pairs.push((key, value)),
+        }
+    }
+}
+
+/// Returns the key with the characters that can't appear in a logfmt key replaced.
+fn format_key(key: &str) -> String {
+    key.chars()
+        .map(|c| match c {
+            ' ' | '=' | '"' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Returns the value quoted and escaped if it is empty or contains whitespace, `=`, `"` or
+/// control characters. Otherwise, the value is returned as is.
+fn format_value(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == '=' || c == '"' || c == '\\');
+
+    if !needs_quotes {
+        return value.to_string();
+    }
+
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:04x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_quote_values() {
+        assert_eq!(format_value("plain"), "plain");
+        assert_eq!(format_value(""), "\"\"");
+        assert_eq!(format_value("two words"), "\"two words\"");
+        assert_eq!(format_value("a=b"), "\"a=b\"");
+        assert_eq!(
+            format_value("say \"hi\"\nC:\\"),
+            "\"say \\\"hi\\\"\\nC:\\\\\""
+        );
+        assert_eq!(format_key("odd key=1"), "odd_key_1");
+    }
+
+    #[test]
+    fn can_flatten_nested_objects() {
+        let record: Map<String, Value> = serde_json::from_str(
+            r#"{"req": {"method": "GET", "headers": {"host": "a"}}, "tags": [1, 2],
+                "empty": {}, "gone": null}"#,
+        )
+        .unwrap();
+        let mut pairs = Vec::new();
+        flatten(&record, "", &mut pairs);
+
+        let pairs: Vec<(&str, String)> = pairs
+            .iter()
+            .map(|(key, value)| (key.as_str(), string_or_value!(value)))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("req.method", "GET".to_string()),
+                ("req.headers.host", "a".to_string()),
+                ("tags", "[1,2]".to_string()),
+                ("empty", "{}".to_string()),
+                ("gone", "null".to_string())
+            ]
+        );
+    }
+}
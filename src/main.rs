@@ -1,15 +1,23 @@
 #[macro_use]
 extern crate clap;
 extern crate bunyan_view;
+extern crate chrono;
 extern crate flate2;
 extern crate pager;
 
-use bunyan_view::{ConditionFilter, LogFormat, LogLevel, LoggerOutputConfig};
+use bunyan_view::{
+    CollapseConfig, ConditionFilter, ContextConfig, DirWatcher, FieldFilter, FieldProjection,
+    GrepFilter, LevelFilter, LogFormat, LoggerOutputConfig, RateLimitConfig, ReadSegment,
+    ReadState, RecordTransformer, RequestTracer, SampleConfig, Template, TimeRange, WatchConfig,
+    DEFAULT_COLUMNS, DEFAULT_TEMPLATE,
+};
+use chrono::Utc;
 use clap::{App, AppSettings, Arg, ArgMatches};
 use flate2::read::GzDecoder;
 use pager::Pager;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
 
 fn main() {
     let env_var_help = "Environment Variables:
@@ -30,17 +38,47 @@ fn main() {
             .takes_value(false)
             .required(false))
         .arg(Arg::with_name("strict")
-            .help("Suppress all but legal Bunyan JSON log lines. By default non-JSON, and non-Bunyan lines are passed through.")
+            .help("Suppress all but legal Bunyan JSON log lines. By default non-JSON, and non-Bunyan lines are passed through, or written to stderr when converting to another structured format (e.g. `-o logfmt` or `-o otlp-json`).")
             .long("strict")
             .takes_value(false)
             .required(false))
         .arg(Arg::with_name("level")
             .help("Only show messages at or above the specified level.
-You can specify level *names* or the internal numeric values.")
+You can specify level *names* or the internal numeric values.
+Levels can be set per logger `name` or `component`, e.g. \"info,db=debug,http.client=warn\".")
             .long("level")
             .short("l")
             .takes_value(true)
             .required(false))
+        .arg(Arg::with_name("level-max")
+            .help("Only show messages at or below the specified level.")
+            .long("level-max")
+            .takes_value(true)
+            .value_name("level")
+            .required(false))
+        .arg(Arg::with_name("only-level")
+            .help("Only show messages at exactly the specified level(s), e.g. \"error,fatal\".")
+            .long("only-level")
+            .takes_value(true)
+            .value_name("level")
+            .required(false))
+        .arg(Arg::with_name("since")
+            .help("Only show messages written at or after the specified time, given as a timestamp (e.g. 2020-01-01T12:00:00Z) or a duration ago (e.g. 15m, 2h).")
+            .long("since")
+            .takes_value(true)
+            .value_name("time")
+            .required(false))
+        .arg(Arg::with_name("until")
+            .help("Only show messages written at or before the specified time, given as a timestamp or a duration ago.")
+            .long("until")
+            .takes_value(true)
+            .value_name("time")
+            .required(false))
+        .arg(Arg::with_name("index")
+            .help("Keep an index alongside each uncompressed input file (e.g. app.log.bvidx) to skip the parts of the file that can't match the --since, --until, level and name or component --field filters.")
+            .long("index")
+            .takes_value(false)
+            .required(false))
         .arg(Arg::with_name("condition")
             .help(r#"Run each log message through the condition and only show those that return truish.
 E.g.:
@@ -50,10 +88,191 @@ E.g.:
 "CONDITION" must be (somewhat) legal JS code.
 `this` holds the log record.
 The TRACE, DEBUG, ... FATAL values are defined to help with comparing `this.level`.
+Multiple conditions may be given, in which case all of them must match.
             "#)
             .long("condition")
             .short("c")
             .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .required(false))
+        .arg(Arg::with_name("field")
+            .help(r#"Only show messages where the field matches. Fields are referenced by dotted paths.
+E.g.:
+  --field 'component=db'
+  --field 'req.url~^/users/'
+  --field 'res.statusCode!=200'
+Use `=` for equality, `!=` for inequality and `~` for regular expression matching.
+Multiple fields may be given, in which case all of them must match.
+            "#)
+            .long("field")
+            .takes_value(true)
+            .value_name("expression")
+            .multiple(true)
+            .number_of_values(1)
+            .required(false))
+        .arg(Arg::with_name("grep")
+            .help("Only show messages where `msg` matches the regular expression. Matches are highlighted.")
+            .long("grep")
+            .short("g")
+            .takes_value(true)
+            .value_name("regex")
+            .required(false))
+        .arg(Arg::with_name("grep-all")
+            .help("Match the --grep regular expression against all string values rather than only `msg`.")
+            .long("grep-all")
+            .takes_value(false)
+            .requires("grep")
+            .required(false))
+        .arg(Arg::with_name("ignore-case")
+            .help("Match the --grep regular expression case insensitively.")
+            .long("ignore-case")
+            .short("i")
+            .takes_value(false)
+            .requires("grep")
+            .required(false))
+        .arg(Arg::with_name("invert-match")
+            .help("Only show messages that do not match the --grep regular expression.")
+            .long("invert-match")
+            .takes_value(false)
+            .requires("grep")
+            .required(false))
+        .arg(Arg::with_name("after-context")
+            .help("Show the specified number of messages after each matching message.")
+            .long("after-context")
+            .short("A")
+            .takes_value(true)
+            .value_name("num")
+            .required(false))
+        .arg(Arg::with_name("before-context")
+            .help("Show the specified number of messages before each matching message.")
+            .long("before-context")
+            .short("B")
+            .takes_value(true)
+            .value_name("num")
+            .required(false))
+        .arg(Arg::with_name("context")
+            .help("Show the specified number of messages before and after each matching message.")
+            .long("context")
+            .short("C")
+            .takes_value(true)
+            .value_name("num")
+            .required(false))
+        .arg(Arg::with_name("context-time")
+            .help("Show all messages within the specified time of each matching message (e.g. 500ms, 5s, 2m).")
+            .long("context-time")
+            .takes_value(true)
+            .value_name("duration")
+            .required(false))
+        .arg(Arg::with_name("head")
+            .help("Only show the first N matching messages of each input.")
+            .long("head")
+            .takes_value(true)
+            .value_name("N")
+            .conflicts_with("tail")
+            .required(false))
+        .arg(Arg::with_name("tail")
+            .help("Only show the last N matching messages of each input. Can't be combined with sampling or rate limiting, which depend on the messages before.")
+            .long("tail")
+            .takes_value(true)
+            .value_name("N")
+            .conflicts_with_all(&["sample", "rate-limit"])
+            .required(false))
+        .arg(Arg::with_name("rotated")
+            .help("Include the rotated copies of each input file (e.g. app.log.1, app.log.2.gz), reading the oldest first.")
+            .long("rotated")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("state")
+            .help("File in which the position each input file has been read up to is recorded.")
+            .long("state")
+            .takes_value(true)
+            .value_name("file")
+            .required(false))
+        .arg(Arg::with_name("since-last-run")
+            .help("Only show messages added to the input files since the previous run with the same --state file.")
+            .long("since-last-run")
+            .takes_value(false)
+            .requires("state")
+            .required(false))
+        .arg(Arg::with_name("collapse")
+            .help("Collapse consecutive messages with the same level, name and message into a single message followed by a count of repeats.")
+            .long("collapse")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("collapse-window")
+            .help("Collapse messages with the same level, name and message that repeat within the specified time (e.g. 30s, 5m), even when not consecutive.")
+            .long("collapse-window")
+            .takes_value(true)
+            .value_name("duration")
+            .required(false))
+        .arg(Arg::with_name("sample")
+            .help("Only show a deterministic sample of the messages, e.g. 1/100 shows 1 out of every 100 messages.")
+            .long("sample")
+            .takes_value(true)
+            .value_name("rate")
+            .required(false))
+        .arg(Arg::with_name("sample-key")
+            .help("Sample messages by the hash of the specified field (e.g. req_id), so that all messages sharing a value are shown or suppressed together.")
+            .long("sample-key")
+            .takes_value(true)
+            .value_name("key")
+            .requires("sample")
+            .required(false))
+        .arg(Arg::with_name("rate-limit")
+            .help("Only show up to the specified number of messages per period of time, e.g. 50/s or 1000/m.")
+            .long("rate-limit")
+            .takes_value(true)
+            .value_name("rate")
+            .required(false))
+        .arg(Arg::with_name("trace-req")
+            .help("Show all messages with the specified request id [req_id] or correlation key value, in time order.")
+            .long("trace-req")
+            .takes_value(true)
+            .value_name("id")
+            .conflicts_with("trace-all")
+            .required(false))
+        .arg(Arg::with_name("trace-all")
+            .help("Show the messages of every request grouped by request id [req_id] or correlation key value, in time order.")
+            .long("trace-all")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("watch")
+            .help("Follow every file in the directory matching --watch-pattern, including files created later, labelling output with the file name.")
+            .long("watch")
+            .takes_value(true)
+            .value_name("dir")
+            .conflicts_with_all(&["FILE", "tail", "state", "trace-req", "trace-all"])
+            .required(false))
+        .arg(Arg::with_name("watch-pattern")
+            .help("Pattern of the names of the files to follow in the --watch directory, e.g. \"job-*.log\".")
+            .long("watch-pattern")
+            .takes_value(true)
+            .value_name("pattern")
+            .requires("watch")
+            .required(false))
+        .arg(Arg::with_name("correlation-key")
+            .help("Additional field used to correlate the messages of a request (e.g. trace_id) when tracing requests.")
+            .long("correlation-key")
+            .takes_value(true)
+            .value_name("key")
+            .multiple(true)
+            .number_of_values(1)
+            .required(false))
+        .arg(Arg::with_name("eval")
+            .help(r#"Run each log message through the expression before it is displayed in
+order to add, rename or remove fields.
+E.g.:
+  -e 'this.user = this.req.headers["x-user"]; delete this.req.headers'
+"EXPRESSION" must be (somewhat) legal JS code.
+`this` holds the log record.
+Multiple expressions may be given and are applied in order.
+            "#)
+            .long("eval")
+            .short("e")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
             .required(false))
         .arg(Arg::with_name("pager")
             .help("Pipe output into `less` (or $PAGER if set), if stdout is a TTY. This overrides $BUNYAN_NO_PAGER.")
@@ -83,12 +302,59 @@ The TRACE, DEBUG, ... FATAL values are defined to help with comparing `this.leve
   json-N: JSON output, N-space indent, e.g. \"json-4\"
   long: (the default) pretty
   short: like \"long\", but more concise
-  simple: level, followed by \"-\" and then the message")
+  simple: level, followed by \"-\" and then the message
+  template: a single line laid out by --template
+  logfmt: key=value pairs with nested fields flattened into dotted keys
+  csv: comma separated values of the --columns with a header row
+  tsv: tab separated values of the --columns with a header row
+  html: self-contained HTML report with level and text filtering
+  markdown: a table of each record followed by fenced code blocks
+  otlp-json: OpenTelemetry logs in the OTLP/JSON encoding, one request per line
+  gelf: GELF 1.1 messages for Graylog with `_` prefixed additional fields
+  syslog: RFC 5424 syslog lines with the extra fields as structured data
+  ecs: Elastic Common Schema documents, see --es-bulk-index
+  combined: Apache combined access log lines of the records with a req and res")
             .long("output")
             .short("o")
             .takes_value(true)
             .value_name("mode")
             .required(false))
+        .arg(Arg::with_name("template")
+            .help("Layout of each message for `-o template`, e.g. '{time:%H:%M:%S} {level:>5|level} [{name}/{component?}] {msg} {req_id?}'.
+Fields are referenced by dotted path and may be given a width and alignment ({level:>5}), a maximum width ({msg:.80}) and styles ({msg|cyan|bold}).
+A trailing ? omits a missing field, and {*} is replaced by the remaining fields as key=value.")
+            .long("template")
+            .takes_value(true)
+            .value_name("template")
+            .conflicts_with_all(&["json-mode", "bunyan-mode"])
+            .required(false))
+        .arg(Arg::with_name("columns")
+            .help("Comma separated fields written by `-o csv` and `-o tsv`, e.g. \"time,level,name,req_id,req.url,res.statusCode,latency\".")
+            .long("columns")
+            .takes_value(true)
+            .value_name("fields")
+            .required(false))
+        .arg(Arg::with_name("fields")
+            .help("Comma separated fields kept by `-o bunyan` and `-o json`, e.g. \"req.url,res.statusCode\".
+The core Bunyan fields (v, level, name, hostname, pid, time and msg) are always kept.")
+            .long("fields")
+            .takes_value(true)
+            .value_name("fields")
+            .required(false))
+        .arg(Arg::with_name("exclude-fields")
+            .help("Comma separated fields removed by `-o bunyan` and `-o json`, e.g. \"req.headers,res.headers\".")
+            .long("exclude-fields")
+            .takes_value(true)
+            .value_name("fields")
+            .required(false))
+        .arg(Arg::with_name("es-bulk-index")
+            .help("Precede each document written by `-o ecs` with an Elasticsearch `_bulk` API action creating it in the index.
+E.g.: bunyan -o ecs --es-bulk-index logs app.log > bulk.ndjson
+  curl -H 'Content-Type: application/x-ndjson' -XPOST localhost:9200/_bulk --data-binary @bulk.ndjson")
+            .long("es-bulk-index")
+            .takes_value(true)
+            .value_name("index")
+            .required(false))
         .arg(Arg::with_name("json-mode")
             .help("shortcut for `-o json`")
             .short("j")
@@ -112,18 +378,112 @@ The TRACE, DEBUG, ... FATAL values are defined to help with comparing `this.leve
             .index(1))
         .get_matches();
 
-    let level: Option<u16> = match matches.value_of("level") {
-        Some(level_string) => match LogLevel::parse(level_string) {
-            Ok(level) => Some(level.as_u16()),
+    let level = parse_level_settings(&matches);
+
+    let time_range = if matches.is_present("since") || matches.is_present("until") {
+        match TimeRange::parse(
+            matches.value_of("since"),
+            matches.value_of("until"),
+            Utc::now(),
+        ) {
+            Ok(time_range) => Some(time_range),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    let condition_filter = match matches.values_of("condition") {
+        Some(conditions) => match ConditionFilter::new(conditions) {
+            Ok(condition_filter) => Some(condition_filter),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let field_filter = match matches.values_of("field") {
+        Some(expressions) => match FieldFilter::new(expressions) {
+            Ok(field_filter) => Some(field_filter),
             Err(e) => {
-                eprintln!("{e}: {level_string}");
+                eprintln!("{e}");
                 std::process::exit(1);
             }
         },
         None => None,
     };
 
-    let condition_filter = matches.value_of("condition").map(ConditionFilter::new);
+    let grep_filter = match matches.value_of("grep") {
+        Some(pattern) => match GrepFilter::new(
+            pattern,
+            matches.is_present("grep-all"),
+            matches.is_present("ignore-case"),
+            matches.is_present("invert-match"),
+        ) {
+            Ok(grep_filter) => Some(grep_filter),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let record_transformer = match matches.values_of("eval") {
+        Some(expressions) => match RecordTransformer::new(expressions) {
+            Ok(record_transformer) => Some(record_transformer),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let context = parse_context_settings(&matches);
+
+    let sample = match matches.value_of("sample") {
+        Some(spec) => {
+            match SampleConfig::parse(spec, matches.value_of("sample-key").map(String::from)) {
+                Ok(sample) => Some(sample),
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+
+    let rate_limit = match matches.value_of("rate-limit") {
+        Some(spec) => match RateLimitConfig::parse(spec) {
+            Ok(rate_limit) => Some(rate_limit),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let collapse = match matches.value_of("collapse-window") {
+        Some(window) => match bunyan_view::parse_duration(window) {
+            Ok(window) => Some(CollapseConfig {
+                window: Some(window),
+            }),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        },
+        None if matches.is_present("collapse") => Some(CollapseConfig::default()),
+        None => None,
+    };
 
     let format = match matches.value_of("output") {
         Some(output_string) => match output_string.to_ascii_lowercase().as_ref() {
@@ -133,6 +493,17 @@ The TRACE, DEBUG, ... FATAL values are defined to help with comparing `this.leve
             "long" => LogFormat::Long,
             "short" => LogFormat::Short,
             "simple" => LogFormat::Simple,
+            "template" => parse_template(&matches),
+            "logfmt" => LogFormat::Logfmt,
+            "csv" => LogFormat::Csv(parse_columns(&matches)),
+            "tsv" => LogFormat::Tsv(parse_columns(&matches)),
+            "html" => LogFormat::Html,
+            "markdown" => LogFormat::Markdown,
+            "otlp-json" => LogFormat::OtlpJson,
+            "gelf" => LogFormat::Gelf,
+            "syslog" => LogFormat::Syslog,
+            "combined" => LogFormat::Combined,
+            "ecs" => LogFormat::Ecs(matches.value_of("es-bulk-index").map(String::from)),
             _mode => {
                 eprintln!("error: unknown output mode: \"{_mode}\"");
                 std::process::exit(1);
@@ -144,27 +515,129 @@ The TRACE, DEBUG, ... FATAL values are defined to help with comparing `this.leve
                 LogFormat::Json(2)
             } else if matches.is_present("bunyan-mode") {
                 LogFormat::Json(0)
+            } else if matches.is_present("template") {
+                parse_template(&matches)
             } else {
                 LogFormat::Long
             }
         }
     };
 
+    if matches.is_present("template") && !matches!(format, LogFormat::Template(_)) {
+        eprintln!("error: --template can only be used with `-o template`");
+        std::process::exit(1);
+    }
+
+    if matches.is_present("columns") && !matches!(format, LogFormat::Csv(_) | LogFormat::Tsv(_)) {
+        eprintln!("error: --columns can only be used with `-o csv` or `-o tsv`");
+        std::process::exit(1);
+    }
+
+    if matches.is_present("es-bulk-index") && !matches!(format, LogFormat::Ecs(_)) {
+        eprintln!("error: --es-bulk-index can only be used with `-o ecs`");
+        std::process::exit(1);
+    }
+
+    let projection = if matches.is_present("fields") || matches.is_present("exclude-fields") {
+        if !matches!(format, LogFormat::Json(_)) {
+            eprintln!("error: --fields and --exclude-fields can only be used with `-o bunyan` or `-o json`");
+            std::process::exit(1);
+        }
+
+        match FieldProjection::new(
+            matches.value_of("fields"),
+            matches.value_of("exclude-fields"),
+        ) {
+            Ok(projection) => Some(projection),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
     let output_config = LoggerOutputConfig {
         indent: 4,
         is_strict: matches.is_present("strict"),
         is_debug: matches.is_present("debug"),
         level,
+        time_range,
         condition_filter,
+        field_filter,
+        grep_filter,
+        record_transformer,
+        projection,
+        context,
+        collapse,
+        sample,
+        rate_limit,
+        head: parse_count(&matches, "head"),
+        tail: parse_count(&matches, "tail"),
+        use_index: matches.is_present("index"),
         display_local_time: matches.is_present("time-local"),
         format,
     };
 
     apply_color_settings(&matches);
 
+    // Markdown is pasted elsewhere, where color codes can't be displayed
+    if output_config.format == LogFormat::Markdown {
+        colored::control::set_override(false);
+    }
+
+    bunyan_view::write_output_header(&mut std::io::stdout(), &output_config);
+
+    // Follow the files of a directory until the process is terminated or --head is satisfied
+    if let Some(dir) = matches.value_of("watch") {
+        let config = WatchConfig::new(dir, matches.value_of("watch-pattern").unwrap_or("*"));
+        let result = DirWatcher::new(config)
+            .and_then(|mut watcher| watcher.watch(&mut std::io::stdout(), &output_config));
+
+        if let Err(e) = result {
+            eprintln!("{e}: {dir}");
+            std::process::exit(1);
+        }
+
+        // Watching only ends once enough records have been written for --head
+        bunyan_view::write_output_footer(&mut std::io::stdout(), &output_config);
+        return;
+    }
+
+    let mut tracer = if matches.is_present("trace-req") || matches.is_present("trace-all") {
+        let keys = matches.values_of("correlation-key").into_iter().flatten();
+        let id = matches.value_of("trace-req").map(String::from);
+        Some(RequestTracer::new(id, keys))
+    } else {
+        None
+    };
+
+    // Traced requests are collected from the whole of each input
+    let is_tracing = tracer.is_some();
+
+    let mut process_input = |reader: Box<dyn BufRead>| match tracer.as_mut() {
+        Some(tracer) => bunyan_view::collect_trace_records(reader, &output_config, tracer),
+        None => bunyan_view::write_bunyan_output(&mut std::io::stdout(), reader, &output_config),
+    };
+
+    let mut read_state =
+        matches
+            .value_of("state")
+            .map(|state_path| match ReadState::load(state_path) {
+                Ok(read_state) => read_state,
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                }
+            });
+    let since_last_run = matches.is_present("since-last-run");
+
     match matches.values_of("FILE") {
         Some(filenames) => {
-            for filename in filenames {
+            let filenames = expand_filenames(filenames, matches.is_present("rotated"));
+
+            for filename in filenames.iter().map(String::as_str) {
                 let file_result = File::open(filename);
 
                 match file_result {
@@ -180,20 +653,248 @@ The TRACE, DEBUG, ... FATAL values are defined to help with comparing `this.leve
                 // We only enable pager support when a file has been directly specified
                 apply_pager_settings(&matches);
 
-                let reader: Box<dyn BufRead> = if filename.ends_with(".gz") {
-                    Box::new(BufReader::new(GzDecoder::new(BufReader::new(file))))
+                // Only read what has been added since the checkpoint of the previous run
+                if let (Some(read_state), true) = (read_state.as_mut(), since_last_run) {
+                    let segments = read_state.unread_segments(filename).and_then(|segments| {
+                        segments
+                            .iter()
+                            .map(open_segment)
+                            .collect::<std::io::Result<Vec<_>>>()
+                    });
+
+                    match segments {
+                        Ok(readers) => readers.into_iter().for_each(&mut process_input),
+                        Err(e) => {
+                            eprintln!("{e}: {filename}");
+                            std::process::exit(1);
+                        }
+                    }
+                    continue;
+                }
+
+                if is_tracing {
+                    let reader: Box<dyn BufRead> = if filename.ends_with(".gz") {
+                        Box::new(BufReader::new(GzDecoder::new(BufReader::new(file))))
+                    } else {
+                        Box::new(BufReader::new(file))
+                    };
+                    process_input(reader);
                 } else {
-                    Box::new(BufReader::new(file))
-                };
+                    let result = bunyan_view::write_bunyan_file(
+                        &mut std::io::stdout(),
+                        Path::new(filename),
+                        &output_config,
+                    );
+
+                    if let Err(e) = result {
+                        eprintln!("{e}: {filename}");
+                        std::process::exit(1);
+                    }
+                }
 
-                bunyan_view::write_bunyan_output(&mut std::io::stdout(), reader, &output_config);
+                if let Some(read_state) = read_state.as_mut() {
+                    if let Err(e) = read_state.mark_read(filename) {
+                        eprintln!("{e}: {filename}");
+                        std::process::exit(1);
+                    }
+                }
             }
         }
         None => {
             let reader = Box::new(BufReader::new(std::io::stdin()));
-            bunyan_view::write_bunyan_output(&mut std::io::stdout(), reader, &output_config);
+            process_input(reader);
         }
     }
+
+    if let Some(read_state) = read_state {
+        if let Err(e) = read_state.save() {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
+
+    // Requests are only written once all inputs have been read
+    if let Some(tracer) = tracer {
+        bunyan_view::write_trace_output(&mut std::io::stdout(), tracer, &output_config);
+    }
+
+    bunyan_view::write_output_footer(&mut std::io::stdout(), &output_config);
+}
+
+/// Returns the input files to read, preceding each file with its rotated copies from oldest
+/// to newest when requested.
+///
+/// # Arguments
+/// * `filenames` - Input files specified on the command line
+/// * `include_rotated` - Whether to include the rotated copies of each file
+fn expand_filenames<'a, I>(filenames: I, include_rotated: bool) -> Vec<String>
+where
+    I: Iterator<Item = &'a str>,
+{
+    filenames
+        .flat_map(|filename| {
+            let family = if include_rotated {
+                bunyan_view::rotated_family(filename).ok()
+            } else {
+                None
+            };
+
+            // A file that can't be found is reported when it is opened
+            match family {
+                Some(family) => family
+                    .iter()
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .collect(),
+                None => vec![filename.to_string()],
+            }
+        })
+        .collect()
+}
+
+/// Opens the range of bytes of a file described by the segment.
+///
+/// # Arguments
+/// * `segment` - Path and range of bytes of the file to read
+fn open_segment(segment: &ReadSegment) -> std::io::Result<Box<dyn BufRead>> {
+    let mut file = File::open(&segment.path)?;
+    let length = segment.end - segment.start;
+
+    // The offsets of compressed files are within their decompressed contents
+    if segment
+        .path
+        .extension()
+        .is_some_and(|extension| extension == "gz")
+    {
+        let mut decoder = GzDecoder::new(BufReader::new(file));
+        std::io::copy(
+            &mut decoder.by_ref().take(segment.start),
+            &mut std::io::sink(),
+        )?;
+        return Ok(Box::new(BufReader::new(decoder.take(length))));
+    }
+
+    file.seek(SeekFrom::Start(segment.start))?;
+    Ok(Box::new(BufReader::new(file.take(length))))
+}
+
+/// Parses the template of `-o template`, using the default template when none was specified.
+///
+/// # Arguments
+/// * `matches` - CLAP flags data structure
+fn parse_template(matches: &ArgMatches) -> LogFormat {
+    match Template::parse(matches.value_of("template").unwrap_or(DEFAULT_TEMPLATE)) {
+        Ok(template) => LogFormat::Template(template),
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Returns the columns of `-o csv` and `-o tsv`, using the default columns when none were
+/// specified.
+///
+/// # Arguments
+/// * `matches` - CLAP flags data structure
+fn parse_columns(matches: &ArgMatches) -> Vec<String> {
+    let columns: Vec<String> = match matches.value_of("columns") {
+        Some(columns) => columns
+            .split(',')
+            .map(str::trim)
+            .filter(|column| !column.is_empty())
+            .map(str::to_string)
+            .collect(),
+        None => DEFAULT_COLUMNS
+            .iter()
+            .map(|column| column.to_string())
+            .collect(),
+    };
+
+    if columns.is_empty() {
+        eprintln!("error: --columns must name at least one field");
+        std::process::exit(1);
+    }
+
+    columns
+}
+
+/// Reads the CLI parameters related to filtering messages by level and returns the resulting
+/// filter if any were specified.
+///
+/// # Arguments
+/// * `matches` - CLAP flags data structure
+fn parse_level_settings(matches: &ArgMatches) -> Option<LevelFilter> {
+    if !(matches.is_present("level")
+        || matches.is_present("level-max")
+        || matches.is_present("only-level"))
+    {
+        return None;
+    }
+
+    let mut result = LevelFilter::parse(matches.value_of("level").unwrap_or_default());
+
+    if let Some(level_string) = matches.value_of("level-max") {
+        result = result.and_then(|level_filter| level_filter.with_maximum(level_string));
+    }
+
+    if let Some(level_string) = matches.value_of("only-level") {
+        result = result.and_then(|level_filter| level_filter.with_only(level_string));
+    }
+
+    match result {
+        Ok(level_filter) => Some(level_filter),
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Reads the CLI parameters related to displaying the messages surrounding matching messages
+/// and returns the resulting settings if any were specified.
+///
+/// # Arguments
+/// * `matches` - CLAP flags data structure
+fn parse_context_settings(matches: &ArgMatches) -> Option<ContextConfig> {
+    let context = parse_count(matches, "context");
+    let before = parse_count(matches, "before-context").or(context);
+    let after = parse_count(matches, "after-context").or(context);
+    let time_window = matches.value_of("context-time").map(|duration_string| {
+        match bunyan_view::parse_duration(duration_string) {
+            Ok(duration) => duration,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+    });
+
+    if before.is_none() && after.is_none() && time_window.is_none() {
+        return None;
+    }
+
+    Some(ContextConfig {
+        before: before.unwrap_or(0),
+        after: after.unwrap_or(0),
+        time_window,
+    })
+}
+
+/// Reads a CLI parameter containing a count of messages, exiting if it isn't a number.
+///
+/// # Arguments
+/// * `matches` - CLAP flags data structure
+/// * `name` - Name of the CLI parameter
+fn parse_count(matches: &ArgMatches, name: &str) -> Option<usize> {
+    matches
+        .value_of(name)
+        .map(|count_string| match count_string.parse::<usize>() {
+            Ok(count) => count,
+            Err(e) => {
+                eprintln!("{e}: {count_string}");
+                std::process::exit(1);
+            }
+        })
 }
 
 /// Reads the CLI parameters and environment variables set upon execution and selectively
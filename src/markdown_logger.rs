@@ -0,0 +1,124 @@
+use std::io::Write;
+
+use chrono::{Local, SecondsFormat};
+
+use crate::errors::ParseResult;
+use crate::formatting_logger::record_sections;
+use crate::{BunyanLine, LogLevel, LoggerOutputConfig};
+
+/// Writes a log record as Markdown that can be pasted into issues and documents. The time,
+/// level, name and message are written as a table, followed by fenced code blocks for the
+/// HTTP requests and responses, the stack trace and any details that don't fit in the table.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `log` - Log record to write
+/// * `output_config` - Configuration containing the time zone to display
+///
+/// # Errors
+///
+/// If the record isn't in the structure expected by the long format, the error is returned
+/// and nothing is written.
+///
+pub fn write_markdown_line<W: Write>(
+    writer: &mut W,
+    log: &BunyanLine,
+    output_config: &LoggerOutputConfig,
+) -> ParseResult {
+    let sections = record_sections(log)?;
+
+    let time = if output_config.display_local_time {
+        log.time
+            .with_timezone(&Local)
+            .to_rfc3339_opts(SecondsFormat::Millis, true)
+    } else {
+        log.time.to_rfc3339_opts(SecondsFormat::Millis, true)
+    };
+
+    let mut name = log.name.clone();
+    if let Some(component) = &log.component {
+        name.push('/');
+        name.push_str(component);
+    }
+
+    // Multiline messages are written in full in the details
+    let msg = format!(
+        "{}{}",
+        log.msg.lines().next().unwrap_or_default(),
+        sections.extras
+    );
+
+    wln!(writer, "| time | level | name | msg |");
+    wln!(writer, "| --- | --- | --- | --- |");
+    wln!(
+        writer,
+        "| {} | {} | {} | {} |",
+        time,
+        LogLevel::from(log.level).as_string(),
+        escape_cell(&name),
+        escape_cell(&msg)
+    );
+
+    if !sections.http.is_empty() {
+        write_code_block(writer, "http", &sections.http.join("\n\n"));
+    }
+
+    if !sections.stack.is_empty() {
+        write_code_block(writer, "", &sections.stack);
+    }
+
+    for detail in &sections.details {
+        write_code_block(writer, "", detail);
+    }
+
+    wln!(writer);
+
+    Ok(())
+}
+
+/// Returns the text escaped so that it can be written within a table cell.
+fn escape_cell(text: &str) -> String {
+    text.trim()
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\r', '\n'], " ")
+}
+
+/// Writes the text as a fenced code block. The fence is made longer than any run of
+/// backticks within the text so that the text can't end the block early.
+fn write_code_block<W: Write>(writer: &mut W, language: &str, text: &str) {
+    let longest_run = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    wln!(writer);
+    wln!(writer, "{}{}", fence, language);
+    wln!(writer, "{}", text.trim_end());
+    wln!(writer, "{}", fence);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_escape_table_cells() {
+        assert_eq!(escape_cell("a | b\nc"), "a \\| b c");
+        assert_eq!(escape_cell(" C:\\temp "), "C:\\\\temp");
+    }
+
+    #[test]
+    fn can_fence_text_containing_backticks() {
+        let mut writer = Vec::new();
+        write_code_block(&mut writer, "", "run ```sh``` then ````");
+
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            "\n`````\nrun ```sh``` then ````\n`````\n"
+        );
+    }
+}
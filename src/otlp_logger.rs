@@ -0,0 +1,186 @@
+use std::io::Write;
+
+use serde_json::map::Map;
+use serde_json::{json, Value};
+
+use crate::{BunyanLine, LogLevel};
+
+/// Fields holding the trace id that a record was logged within
+const TRACE_ID_FIELDS: [&str; 2] = ["trace_id", "traceId"];
+/// Fields holding the span id that a record was logged within
+const SPAN_ID_FIELDS: [&str; 2] = ["span_id", "spanId"];
+
+/// Writes a log record as a single line OTLP/JSON `ExportLogsServiceRequest`, which can be
+/// read by the OpenTelemetry collector (eg with its `otlpjsonfile` receiver). The record is
+/// mapped onto the OpenTelemetry logs data model as follows:
+///
+/// * `level` - `severityNumber` and `severityText`
+/// * `time` - `timeUnixNano`
+/// * `msg` - `body`
+/// * `name`, `hostname` and `pid` - `service.name`, `host.name` and `process.pid` resource
+///   attributes
+/// * `trace_id` and `span_id` (or `traceId` and `spanId`) - `traceId` and `spanId` when
+///   they are hex encoded ids of the expected length
+/// * remaining fields - `attributes`
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `log` - Log record to write
+///
+pub fn write_otlp_line<W: Write>(writer: &mut W, log: &BunyanLine) {
+    let mut other = log.other.clone();
+
+    let (severity_number, severity_text) = severity(log.level);
+    let time_unix_nano = log
+        .time
+        .timestamp_nanos_opt()
+        .map(|nanos| nanos.to_string())
+        .unwrap_or_else(|| "0".to_string());
+
+    let mut record = Map::new();
+    record.insert("timeUnixNano".to_string(), json!(time_unix_nano));
+    record.insert("severityNumber".to_string(), json!(severity_number));
+    record.insert("severityText".to_string(), json!(severity_text));
+    record.insert("body".to_string(), json!({ "stringValue": log.msg }));
+
+    if let Some(trace_id) = take_id(&mut other, &TRACE_ID_FIELDS, 32) {
+        record.insert("traceId".to_string(), json!(trace_id));
+    }
+    if let Some(span_id) = take_id(&mut other, &SPAN_ID_FIELDS, 16) {
+        record.insert("spanId".to_string(), json!(span_id));
+    }
+
+    let mut attributes = Vec::new();
+    if let Some(component) = &log.component {
+        attributes.push(key_value("component", &json!(component)));
+    }
+    attributes.extend(other.iter().map(|(key, value)| key_value(key, value)));
+    record.insert("attributes".to_string(), Value::Array(attributes));
+
+    let request = json!({
+        "resourceLogs": [{
+            "resource": {
+                "attributes": [
+                    key_value("service.name", &json!(log.name)),
+                    key_value("host.name", &json!(log.hostname)),
+                    key_value("process.pid", &json!(log.pid)),
+                ]
+            },
+            "scopeLogs": [{
+                "scope": {},
+                "logRecords": [record]
+            }]
+        }]
+    });
+
+    wln!(writer, "{}", request);
+}
+
+/// Returns the OpenTelemetry severity number and text of a Bunyan level. Custom levels are
+/// given the severity of the named level below them.
+fn severity(level: u16) -> (u8, String) {
+    let number = match level {
+        0..=19 => 1,
+        20..=29 => 5,
+        30..=39 => 9,
+        40..=49 => 13,
+        50..=59 => 17,
+        _ => 21,
+    };
+
+    (number, LogLevel::from(level).as_string().into_owned())
+}
+
+/// Removes and returns the first of the fields holding a hex encoded id of the passed
+/// length. Fields holding anything else are left to be written as attributes.
+fn take_id(other: &mut Map<String, Value>, fields: &[&str], length: usize) -> Option<String> {
+    let field = fields.iter().find(|field| {
+        other
+            .get(**field)
+            .and_then(Value::as_str)
+            .is_some_and(|id| id.len() == length && id.chars().all(|c| c.is_ascii_hexdigit()))
+    })?;
+
+    other
+        .remove(*field)
+        .and_then(|id| id.as_str().map(str::to_ascii_lowercase))
+}
+
+/// Returns an OTLP/JSON `KeyValue` for the passed key and value.
+fn key_value(key: &str, value: &Value) -> Value {
+    json!({ "key": key, "value": any_value(value) })
+}
+
+/// Returns the OTLP/JSON `AnyValue` representation of the passed JSON value. As in the
+/// protobuf JSON mapping, 64 bit integers are written as strings.
+fn any_value(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({}),
+        Value::Bool(flag) => json!({ "boolValue": flag }),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => json!({ "intValue": integer.to_string() }),
+            None => json!({ "doubleValue": number.as_f64() }),
+        },
+        Value::String(text) => json!({ "stringValue": text }),
+        Value::Array(values) => json!({
+            "arrayValue": { "values": values.iter().map(any_value).collect::<Vec<Value>>() }
+        }),
+        Value::Object(map) => json!({
+            "kvlistValue": {
+                "values": map
+                    .iter()
+                    .map(|(key, value)| key_value(key, value))
+                    .collect::<Vec<Value>>()
+            }
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_map_levels_to_severities() {
+        assert_eq!(severity(10), (1, "TRACE".to_string()));
+        assert_eq!(severity(30), (9, "INFO".to_string()));
+        assert_eq!(severity(60), (21, "FATAL".to_string()));
+        assert_eq!(severity(35), (9, "LVL35".to_string()));
+    }
+
+    #[test]
+    fn can_detect_trace_and_span_ids() {
+        let mut other: Map<String, Value> = serde_json::from_str(
+            r#"{"trace_id": "not-a-trace-id", "traceId": "4BF92F3577B34DA6A3CE929D0E0E4736",
+                "span_id": "00f067aa0ba902b7"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            take_id(&mut other, &TRACE_ID_FIELDS, 32),
+            Some("4bf92f3577b34da6a3ce929d0e0e4736".to_string())
+        );
+        assert_eq!(
+            take_id(&mut other, &SPAN_ID_FIELDS, 16),
+            Some("00f067aa0ba902b7".to_string())
+        );
+        assert_eq!(other.keys().collect::<Vec<_>>(), vec!["trace_id"]);
+    }
+
+    #[test]
+    fn can_convert_values_to_any_values() {
+        let value: Value =
+            serde_json::from_str(r#"{"a": [1, 1.5, true, null], "b": {"c": "d"}}"#).unwrap();
+
+        assert_eq!(
+            any_value(&value).to_string(),
+            concat!(
+                r#"{"kvlistValue":{"values":[{"key":"a","value":{"arrayValue":{"values":"#,
+                r#"[{"intValue":"1"},{"doubleValue":1.5},{"boolValue":true},{}]}}},"#,
+                r#"{"key":"b","value":{"kvlistValue":{"values":[{"key":"c","value":"#,
+                r#"{"stringValue":"d"}}]}}}]}}"#
+            )
+        );
+    }
+}
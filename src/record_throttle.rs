@@ -0,0 +1,353 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::map::Map;
+use serde_json::Value;
+
+use crate::context_buffer::parse_duration;
+use crate::errors::RateParseError;
+use crate::field_filter::lookup_path;
+use crate::LogLevel;
+
+/// Settings for keeping a deterministic sample of records
+#[derive(Debug, Clone)]
+pub struct SampleConfig {
+    /// Number of records kept out of every `every` records
+    pub keep: u64,
+    pub every: u64,
+    /// Optional dotted path of a key (eg `req_id`) whose value decides whether a record is
+    /// kept, so that all records sharing the value are kept or dropped together
+    pub key: Option<String>,
+}
+
+impl SampleConfig {
+    /// Parses a sample rate in the form of `1/100`.
+    ///
+    /// # Errors
+    ///
+    /// If the input is not two positive numbers separated by `/` with the first no larger
+    /// than the second, a `RateParseError` is returned.
+    ///
+    pub fn parse(spec: &str, key: Option<String>) -> Result<SampleConfig, RateParseError> {
+        let (keep, every) = spec
+            .split_once('/')
+            .ok_or_else(|| RateParseError::new(spec, "expected a rate such as 1/100"))?;
+
+        let parse_count = |count: &str| match count.trim().parse::<u64>() {
+            Ok(count) if count > 0 => Ok(count),
+            _ => Err(RateParseError::new(
+                spec,
+                format!("[{count}] is not a positive number"),
+            )),
+        };
+
+        let keep = parse_count(keep)?;
+        let every = parse_count(every)?;
+
+        if keep > every {
+            return Err(RateParseError::new(
+                spec,
+                "more records can't be kept than are sampled",
+            ));
+        }
+
+        Ok(SampleConfig { keep, every, key })
+    }
+}
+
+/// Settings for limiting the number of records displayed per period of time
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// Maximum number of records displayed within each period
+    pub limit: usize,
+    pub period: Duration,
+}
+
+impl RateLimitConfig {
+    /// Parses a rate limit in the form of `50/s`, `1000/m` or `10/500ms`.
+    ///
+    /// # Errors
+    ///
+    /// If the input is not a positive number followed by `/` and a duration, a
+    /// `RateParseError` is returned.
+    ///
+    pub fn parse(spec: &str) -> Result<RateLimitConfig, RateParseError> {
+        let (limit, period) = spec
+            .split_once('/')
+            .ok_or_else(|| RateParseError::new(spec, "expected a rate such as 50/s"))?;
+
+        let limit = match limit.trim().parse::<usize>() {
+            Ok(limit) if limit > 0 => limit,
+            _ => {
+                return Err(RateParseError::new(
+                    spec,
+                    format!("[{limit}] is not a positive number"),
+                ))
+            }
+        };
+
+        // Allow a unit without a number (eg `s`) to mean a single unit
+        let period = period.trim();
+        let period = if period.starts_with(|c: char| c.is_ascii_digit()) {
+            parse_duration(period)
+        } else {
+            parse_duration(format!("1{period}"))
+        }
+        .map_err(|e| RateParseError::new(spec, e.to_string()))?;
+
+        if period <= Duration::zero() {
+            return Err(RateParseError::new(
+                spec,
+                "period must be greater than zero",
+            ));
+        }
+
+        Ok(RateLimitConfig { limit, period })
+    }
+}
+
+/// Counts of suppressed records by level
+#[derive(Default)]
+struct SuppressedCounts {
+    levels: BTreeMap<u16, usize>,
+}
+
+impl SuppressedCounts {
+    fn add(&mut self, level: u16) {
+        *self.levels.entry(level).or_insert(0) += 1;
+    }
+
+    /// Returns a line such as `… sampling suppressed 12 records (DEBUG: 10, INFO: 2)` and
+    /// resets the counts or returns `None` if no records were suppressed.
+    fn take_summary(&mut self, reason: &str) -> Option<String> {
+        let total: usize = self.levels.values().sum();
+        if total == 0 {
+            return None;
+        }
+
+        let plural = if total == 1 { "" } else { "s" };
+        let levels: Vec<String> = self
+            .levels
+            .iter()
+            .map(|(level, count)| format!("{}: {}", LogLevel::from(*level).as_string(), count))
+            .collect();
+        self.levels.clear();
+
+        Some(format!(
+            "\u{2026} {} suppressed {} record{} ({})",
+            reason,
+            total,
+            plural,
+            levels.join(", ")
+        ))
+    }
+}
+
+/// Reduces the volume of records displayed by keeping a deterministic sample of records
+/// and limiting the number of records displayed per period of time. Periods are measured
+/// using the timestamps of the records, so the same input always produces the same output.
+/// Summaries of the records suppressed are produced for each period and for the sample
+/// once the end of the input is reached.
+///
+pub struct RecordThrottle {
+    sample: Option<SampleConfig>,
+    rate_limit: Option<RateLimitConfig>,
+    sample_count: u64,
+    sample_suppressed: SuppressedCounts,
+    window_start: Option<DateTime<Utc>>,
+    window_count: usize,
+    rate_suppressed: SuppressedCounts,
+}
+
+impl RecordThrottle {
+    pub fn new(sample: Option<SampleConfig>, rate_limit: Option<RateLimitConfig>) -> Self {
+        Self {
+            sample,
+            rate_limit,
+            sample_count: 0,
+            sample_suppressed: SuppressedCounts::default(),
+            window_start: None,
+            window_count: 0,
+            rate_suppressed: SuppressedCounts::default(),
+        }
+    }
+
+    /// Returns true if the record should be displayed along with the summaries of any
+    /// periods that ended before the record.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - Map containing the JSON data of the log record
+    /// * `level` - Numeric level of the record
+    /// * `time` - Timestamp of the record
+    ///
+    pub fn admit(
+        &mut self,
+        record: &Map<String, Value>,
+        level: u16,
+        time: DateTime<Utc>,
+    ) -> (bool, Vec<String>) {
+        let mut summaries = Vec::new();
+
+        if !self.is_sampled(record) {
+            self.sample_suppressed.add(level);
+            return (false, summaries);
+        }
+
+        if let Some(rate_limit) = &self.rate_limit {
+            let is_new_window = self
+                .window_start
+                .is_none_or(|start| time < start || time - start >= rate_limit.period);
+
+            if is_new_window {
+                summaries.extend(self.rate_suppressed.take_summary("rate limit"));
+                self.window_start = Some(time);
+                self.window_count = 0;
+            }
+
+            if self.window_count >= rate_limit.limit {
+                self.rate_suppressed.add(level);
+                return (false, summaries);
+            }
+
+            self.window_count += 1;
+        }
+
+        (true, summaries)
+    }
+
+    /// Returns the summaries of all records suppressed that have not yet been reported.
+    /// This is called once the end of the input has been reached.
+    pub fn finish(&mut self) -> Vec<String> {
+        self.rate_suppressed
+            .take_summary("rate limit")
+            .into_iter()
+            .chain(self.sample_suppressed.take_summary("sampling"))
+            .collect()
+    }
+
+    fn is_sampled(&mut self, record: &Map<String, Value>) -> bool {
+        let sample = match &self.sample {
+            Some(sample) => sample,
+            None => return true,
+        };
+
+        let key_value = sample
+            .key
+            .as_ref()
+            .and_then(|key| lookup_path(record, key))
+            .filter(|value| !value.is_null())
+            .map(|value| string_or_value!(value));
+
+        let position = match key_value {
+            Some(value) => fnv1a_hash(value.as_bytes()) % sample.every,
+            // Records without the key are sampled in the order they appear
+            None => {
+                let position = self.sample_count % sample.every;
+                self.sample_count += 1;
+                position
+            }
+        };
+
+        position < sample.keep
+    }
+}
+
+/// Returns the 64 bit FNV-1a hash of the passed bytes. This is used rather than the
+/// standard library's hasher because its output is stable across releases and platforms.
+pub(crate) fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(millis: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(millis).unwrap()
+    }
+
+    fn record(json: &str) -> Map<String, Value> {
+        serde_json::from_str(json).expect("invalid test JSON")
+    }
+
+    #[test]
+    fn can_parse_rates() {
+        let sample = SampleConfig::parse("1/100", None).unwrap();
+        assert_eq!((sample.keep, sample.every), (1, 100));
+        assert!(SampleConfig::parse("5/2", None).is_err());
+        assert!(SampleConfig::parse("0/2", None).is_err());
+        assert!(SampleConfig::parse("100", None).is_err());
+
+        let rate_limit = RateLimitConfig::parse("50/s").unwrap();
+        assert_eq!(rate_limit.limit, 50);
+        assert_eq!(rate_limit.period, Duration::seconds(1));
+        assert_eq!(
+            RateLimitConfig::parse("10/500ms").unwrap().period,
+            Duration::milliseconds(500)
+        );
+        assert!(RateLimitConfig::parse("fast/s").is_err());
+        assert!(RateLimitConfig::parse("50/0s").is_err());
+    }
+
+    #[test]
+    fn can_sample_records_in_order() {
+        let mut throttle = RecordThrottle::new(SampleConfig::parse("1/3", None).ok(), None);
+        let kept: Vec<bool> = (0..6)
+            .map(|i| throttle.admit(&record("{}"), 20, at(i)).0)
+            .collect();
+
+        assert_eq!(kept, vec![true, false, false, true, false, false]);
+        assert_eq!(
+            throttle.finish(),
+            vec!["\u{2026} sampling suppressed 4 records (DEBUG: 4)"]
+        );
+    }
+
+    #[test]
+    fn can_sample_whole_requests_by_key() {
+        let sample = SampleConfig::parse("1/2", Some("req_id".to_string())).ok();
+        let mut throttle = RecordThrottle::new(sample, None);
+
+        let kept: Vec<&str> = ["a", "b", "c", "d", "e", "f"]
+            .into_iter()
+            .filter(|id| {
+                let json = format!(r#"{{"req_id": "{id}"}}"#);
+                throttle.admit(&record(&json), 30, at(0)).0
+            })
+            .collect();
+        assert_eq!(kept, vec!["a", "c", "e"]);
+
+        // The decision depends only on the key, not on the rest of the record
+        for (id, expected) in [("a", true), ("b", false)] {
+            for (index, level) in [20, 30, 50].into_iter().enumerate() {
+                let json = format!(r#"{{"req_id": "{id}", "msg": "record {index}"}}"#);
+                let (is_kept, _) = throttle.admit(&record(&json), level, at(index as i64));
+                assert_eq!(is_kept, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn can_rate_limit_records() {
+        let mut throttle = RecordThrottle::new(None, RateLimitConfig::parse("2/s").ok());
+        let results: Vec<(bool, Vec<String>)> = [0, 100, 200, 300, 1000]
+            .iter()
+            .zip([20, 20, 30, 20, 30])
+            .map(|(millis, level)| throttle.admit(&record("{}"), level, at(*millis)))
+            .collect();
+
+        let kept: Vec<bool> = results.iter().map(|(kept, _)| *kept).collect();
+        assert_eq!(kept, vec![true, true, false, false, true]);
+        assert_eq!(
+            results[4].1,
+            vec!["\u{2026} rate limit suppressed 2 records (DEBUG: 1, INFO: 1)"]
+        );
+        assert!(throttle.finish().is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+use core::fmt;
+
+use quick_js::{Context, JsValue};
+use serde_json::map::Map;
+use serde_json::Value;
+
+use crate::condition_filter::JS_LEVEL_CONSTANTS;
+use crate::errors::ScriptCompileError;
+
+/// Name of the global JS array holding the compiled transformation functions
+const TRANSFORMS_GLOBAL: &str = "__bunyan_transforms";
+/// Name of the global JS function that applies all transformations to a record
+const TRANSFORM_FUNCTION: &str = "__bunyan_transform";
+/// JS prelude defining the function used to transform records. Records are passed in and
+/// out as JSON text so that the ordering of keys is preserved. Each expression is run on a
+/// copy of the record, which only replaces the record if the expression doesn't throw.
+const PRELUDE: &str = r#"
+var __bunyan_transforms = [];
+function __bunyan_transform(json) {
+    var record = JSON.parse(json);
+    __bunyan_transforms.forEach(function (transform) {
+        var copy = JSON.parse(JSON.stringify(record));
+        try {
+            transform.call(copy);
+            record = copy;
+        } catch (e) {
+        }
+    });
+    return JSON.stringify(record);
+}
+"#;
+
+/// Modifies log records using one or more JS expressions before they are displayed. Each
+/// expression is compiled once into a function and is run with `this` bound to the record,
+/// so that fields can be added, renamed or deleted. Expressions are applied in the order
+/// given and an expression that throws an exception leaves the record as it was before
+/// that expression was run.
+///
+pub struct RecordTransformer {
+    context: Context,
+    expressions: Vec<String>,
+}
+
+impl RecordTransformer {
+    pub fn new<I, S>(expressions: I) -> Result<Self, ScriptCompileError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let expressions: Vec<String> = expressions.into_iter().map(Into::into).collect();
+        let context = Self::compile(&expressions)?;
+
+        Ok(Self {
+            context,
+            expressions,
+        })
+    }
+
+    /// Creates a new JS context containing the prelude and a compiled function for
+    /// each of the passed expressions.
+    ///
+    /// # Errors
+    ///
+    /// If an expression is not syntactically valid JS, a `ScriptCompileError` is returned
+    /// referencing the offending expression.
+    ///
+    fn compile(expressions: &[String]) -> Result<Context, ScriptCompileError> {
+        let context = Context::new().map_err(|e| ScriptCompileError::new("", e.to_string()))?;
+
+        context
+            .eval(JS_LEVEL_CONSTANTS)
+            .and_then(|_| context.eval(PRELUDE))
+            .map_err(|e| ScriptCompileError::new("", e.to_string()))?;
+
+        for expression in expressions {
+            let code = format!("{TRANSFORMS_GLOBAL}.push(function () {{ {expression}\n; }});");
+            context
+                .eval(code.as_str())
+                .map_err(|e| ScriptCompileError::new(expression.as_str(), e.to_string()))?;
+        }
+
+        Ok(context)
+    }
+
+    /// Returns the passed record after all expressions have been applied to it. If the
+    /// transformed record can't be converted back into a JSON object, `None` is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - Map containing the JSON data of the log record
+    ///
+    pub fn transform(&self, record: &Map<String, Value>) -> Option<Map<String, Value>> {
+        let json = serde_json::to_string(record).ok()?;
+
+        match self.context.call_function(TRANSFORM_FUNCTION, vec![json]) {
+            Ok(JsValue::String(transformed)) => serde_json::from_str(&transformed).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for RecordTransformer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RecordTransformer [`{}`]", self.expressions.join("`; `"))
+    }
+}
+
+impl Clone for RecordTransformer {
+    fn clone(&self) -> Self {
+        Self {
+            context: Self::compile(&self.expressions)
+                .expect("expressions were already compiled successfully"),
+            expressions: self.expressions.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(json: &str) -> Map<String, Value> {
+        serde_json::from_str(json).expect("invalid test JSON")
+    }
+
+    #[test]
+    fn can_derive_and_prune_fields() {
+        let transformer = RecordTransformer::new(vec![
+            r#"this.user = this.req.headers["x-user"]; delete this.req.headers"#,
+        ])
+        .unwrap();
+        let transformed = transformer
+            .transform(&record(
+                r#"{"msg": "hi", "req": {"url": "/", "headers": {"x-user": "bob"}}}"#,
+            ))
+            .unwrap();
+
+        assert_eq!(
+            Value::Object(transformed).to_string(),
+            r#"{"msg":"hi","req":{"url":"/"},"user":"bob"}"#
+        );
+    }
+
+    #[test]
+    fn expressions_are_applied_in_order() {
+        let transformer =
+            RecordTransformer::new(vec!["this.level = WARN", "this.msg += ' ' + this.level"])
+                .unwrap();
+        let transformed = transformer
+            .transform(&record(r#"{"level": 30, "msg": "level"}"#))
+            .unwrap();
+
+        assert_eq!(transformed.get("msg"), Some(&Value::from("level 40")));
+    }
+
+    #[test]
+    fn exceptions_leave_record_unchanged() {
+        let transformer =
+            RecordTransformer::new(vec!["this.user = this.req.headers.user"]).unwrap();
+        let original = record(r#"{"msg": "no req"}"#);
+
+        assert_eq!(transformer.transform(&original), Some(original));
+    }
+
+    #[test]
+    fn exceptions_discard_partial_changes() {
+        let transformer = RecordTransformer::new(vec![
+            "this.msg = 'changed'; delete this.req; this.req.url.length",
+            "this.user = 'bob'",
+        ])
+        .unwrap();
+        let transformed = transformer
+            .transform(&record(r#"{"msg": "hi", "req": {"url": "/"}}"#))
+            .unwrap();
+
+        assert_eq!(
+            Value::Object(transformed).to_string(),
+            r#"{"msg":"hi","req":{"url":"/"},"user":"bob"}"#
+        );
+    }
+
+    #[test]
+    fn invalid_expression_is_an_error() {
+        assert!(RecordTransformer::new(vec!["this.msg = "]).is_err());
+    }
+}
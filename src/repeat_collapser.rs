@@ -0,0 +1,269 @@
+use chrono::{DateTime, Duration, Utc};
+
+/// Settings controlling how repeated records are collapsed
+#[derive(Debug, Clone, Default)]
+pub struct CollapseConfig {
+    /// When set, records repeating within this amount of time of the first occurrence are
+    /// collapsed even if other records appear between them. Otherwise, only consecutive
+    /// records are collapsed.
+    pub window: Option<Duration>,
+}
+
+/// Properties that make two records repeats of each other
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepeatKey {
+    level: u16,
+    name: String,
+    msg: String,
+}
+
+impl RepeatKey {
+    pub fn new(level: u16, name: &str, msg: &str) -> Self {
+        Self {
+            level,
+            name: name.to_string(),
+            msg: normalize_msg(msg),
+        }
+    }
+}
+
+/// A record that has been written along with the repeats of it that have been suppressed
+struct RepeatGroup {
+    key: RepeatKey,
+    msg: String,
+    first_time: DateTime<Utc>,
+    last_time: DateTime<Utc>,
+    count: usize,
+}
+
+impl RepeatGroup {
+    /// Returns a line describing the suppressed repeats or `None` if there weren't any.
+    fn summary(&self, include_msg: bool) -> Option<String> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let plural = if self.count == 1 { "" } else { "s" };
+        let elapsed = (self.last_time - self.first_time).num_milliseconds() as f64 / 1000.0;
+        let mut summary = format!(
+            "\u{2026} repeated {} time{} over {:.1}s",
+            self.count, plural, elapsed
+        );
+
+        if include_msg {
+            summary.push_str(": ");
+            summary.push_str(&self.msg);
+        }
+
+        Some(summary)
+    }
+}
+
+/// Suppresses records that repeat the level, logger name and message of a previous record
+/// and produces summary lines in the form of `… repeated 312 times over 4.2s` in their
+/// place. Numbers within messages are ignored when comparing them so that messages such as
+/// `reconnect attempt 5` and `reconnect attempt 6` are treated as repeats.
+///
+pub struct RepeatCollapser {
+    window: Option<Duration>,
+    groups: Vec<RepeatGroup>,
+}
+
+impl RepeatCollapser {
+    pub fn new(config: CollapseConfig) -> Self {
+        Self {
+            window: config.window,
+            groups: Vec::new(),
+        }
+    }
+
+    /// Adds a record and returns true if it should be written along with any summaries
+    /// that should be written before it.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Properties of the record used to identify repeats
+    /// * `msg` - Message of the record as it will be displayed in summaries
+    /// * `time` - Timestamp of the record
+    ///
+    pub fn push(&mut self, key: RepeatKey, msg: &str, time: DateTime<Utc>) -> (bool, Vec<String>) {
+        let mut summaries = Vec::new();
+
+        match self.window {
+            // Only the last record written can be repeated
+            None => {
+                if let Some(group) = self.groups.first_mut() {
+                    if group.key == key {
+                        group.count += 1;
+                        group.last_time = time;
+                        return (false, summaries);
+                    }
+                }
+
+                summaries.extend(
+                    self.groups
+                        .drain(..)
+                        .filter_map(|group| group.summary(false)),
+                );
+            }
+            // Any record written within the window can be repeated
+            Some(window) => {
+                let (expired, active): (Vec<RepeatGroup>, Vec<RepeatGroup>) = self
+                    .groups
+                    .drain(..)
+                    .partition(|group| time - group.first_time > window);
+                self.groups = active;
+                summaries.extend(expired.iter().filter_map(|group| group.summary(true)));
+
+                if let Some(group) = self.groups.iter_mut().find(|group| group.key == key) {
+                    group.count += 1;
+                    group.last_time = time;
+                    return (false, summaries);
+                }
+            }
+        }
+
+        self.groups.push(RepeatGroup {
+            key,
+            msg: msg.to_string(),
+            first_time: time,
+            last_time: time,
+            count: 0,
+        });
+
+        (true, summaries)
+    }
+
+    /// Returns the summaries of all outstanding repeats. This is called once the end of
+    /// the input has been reached.
+    pub fn finish(&mut self) -> Vec<String> {
+        let include_msg = self.window.is_some();
+        self.groups
+            .drain(..)
+            .filter_map(|group| group.summary(include_msg))
+            .collect()
+    }
+}
+
+/// Returns the message with runs of digits replaced by `#` and whitespace collapsed, so
+/// that messages differing only by counters, ports or durations are considered equal.
+fn normalize_msg(msg: &str) -> String {
+    let mut normalized = String::with_capacity(msg.len());
+    let mut previous: Option<char> = None;
+
+    for c in msg.trim().chars() {
+        let replacement = if c.is_ascii_digit() {
+            '#'
+        } else if c.is_whitespace() {
+            ' '
+        } else {
+            c
+        };
+
+        let is_run = matches!(replacement, '#' | ' ') && previous == Some(replacement);
+        if !is_run {
+            normalized.push(replacement);
+        }
+        previous = Some(replacement);
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(millis: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(millis).unwrap()
+    }
+
+    /// Runs the passed (time in millis, msg) records through a collapser returning the
+    /// written messages and summaries.
+    fn run(config: CollapseConfig, records: Vec<(i64, &str)>) -> Vec<String> {
+        let mut collapser = RepeatCollapser::new(config);
+        let mut written = Vec::new();
+
+        for (millis, msg) in records {
+            let (write, summaries) =
+                collapser.push(RepeatKey::new(30, "app", msg), msg, at(millis));
+            written.extend(summaries);
+            if write {
+                written.push(msg.to_string());
+            }
+        }
+        written.extend(collapser.finish());
+
+        written
+    }
+
+    #[test]
+    fn can_collapse_consecutive_repeats() {
+        let records = vec![
+            (0, "health check"),
+            (1000, "health check"),
+            (4200, "health check"),
+            (5000, "request"),
+            (6000, "health check"),
+        ];
+
+        assert_eq!(
+            run(CollapseConfig::default(), records),
+            vec![
+                "health check",
+                "\u{2026} repeated 2 times over 4.2s",
+                "request",
+                "health check"
+            ]
+        );
+    }
+
+    #[test]
+    fn can_collapse_repeats_within_window() {
+        let config = CollapseConfig {
+            window: Some(Duration::seconds(10)),
+        };
+        let records = vec![
+            (0, "reconnect attempt 1"),
+            (1000, "request"),
+            (2000, "reconnect attempt 2"),
+            (3000, "reconnect attempt 3"),
+            (20000, "reconnect attempt 4"),
+        ];
+
+        assert_eq!(
+            run(config, records),
+            vec![
+                "reconnect attempt 1",
+                "request",
+                "\u{2026} repeated 2 times over 3.0s: reconnect attempt 1",
+                "reconnect attempt 4"
+            ]
+        );
+    }
+
+    #[test]
+    fn different_levels_are_not_repeats() {
+        let mut collapser = RepeatCollapser::new(CollapseConfig::default());
+        assert!(
+            collapser
+                .push(RepeatKey::new(30, "app", "msg"), "msg", at(0))
+                .0
+        );
+        assert!(
+            collapser
+                .push(RepeatKey::new(40, "app", "msg"), "msg", at(0))
+                .0
+        );
+        assert!(
+            collapser
+                .push(RepeatKey::new(40, "db", "msg"), "msg", at(0))
+                .0
+        );
+    }
+
+    #[test]
+    fn can_normalize_msg() {
+        assert_eq!(normalize_msg(" retry 12 in  500ms "), "retry # in #ms");
+    }
+}
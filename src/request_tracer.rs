@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde_json::map::Map;
+use serde_json::Value;
+
+use crate::field_filter::lookup_path;
+
+/// Key that always correlates the records of a request
+const REQ_ID_KEY: &str = "req_id";
+
+/// A log record belonging to a request along with the position it was read from
+pub struct TracedRecord {
+    pub time: DateTime<Utc>,
+    pub line: String,
+    pub line_no: usize,
+    sequence: usize,
+    /// Node of the first correlation id of the record
+    node: usize,
+    method: Option<String>,
+    url: Option<String>,
+    status_code: Option<String>,
+}
+
+impl TracedRecord {
+    /// Records the HTTP method, url and status code found in the `req`, `client_req`,
+    /// `res` or `client_res` of a record.
+    fn read_summary(&mut self, record: &Map<String, Value>) {
+        for key in ["req", "client_req"] {
+            if let Some(req) = record.get(key) {
+                if self.method.is_none() {
+                    self.method = req.get("method").map(|value| string_or_value!(value));
+                }
+                if self.url.is_none() {
+                    self.url = req.get("url").map(|value| string_or_value!(value));
+                }
+            }
+        }
+
+        for key in ["res", "client_res"] {
+            if let Some(status_code) = record.get(key).and_then(|res| res.get("statusCode")) {
+                self.status_code = Some(string_or_value!(status_code));
+            }
+        }
+    }
+}
+
+/// All of the records sharing a correlation id along with a summary of the request
+pub struct Trace {
+    pub id: String,
+    pub records: Vec<TracedRecord>,
+    method: Option<String>,
+    url: Option<String>,
+    status_code: Option<String>,
+}
+
+impl Trace {
+    /// Returns a one line description of the request such as
+    /// `1234: GET /users/42 -> 200 (4 records over 45ms)`.
+    pub fn summary(&self) -> String {
+        let mut summary = self.id.clone();
+        summary.push(':');
+
+        if let Some(method) = &self.method {
+            summary.push(' ');
+            summary.push_str(method);
+        }
+        if let Some(url) = &self.url {
+            summary.push(' ');
+            summary.push_str(url);
+        }
+        if let Some(status_code) = &self.status_code {
+            summary.push_str(" -> ");
+            summary.push_str(status_code);
+        }
+
+        let count = self.records.len();
+        let plural = if count == 1 { "" } else { "s" };
+        let elapsed = match (self.records.first(), self.records.last()) {
+            (Some(first), Some(last)) => (last.time - first.time).num_milliseconds(),
+            _ => 0,
+        };
+
+        summary.push_str(&format!(" ({count} record{plural} over {elapsed}ms)"));
+        summary
+    }
+
+    /// Keeps the first HTTP method and url and the last status code of the records, in the
+    /// order the records were read.
+    fn update_summary(&mut self, record: &TracedRecord) {
+        if self.method.is_none() {
+            self.method.clone_from(&record.method);
+        }
+        if self.url.is_none() {
+            self.url.clone_from(&record.url);
+        }
+        if record.status_code.is_some() {
+            self.status_code.clone_from(&record.status_code);
+        }
+    }
+}
+
+/// Collects the records that belong to requests across all inputs, so that they can be
+/// displayed together in time order. Records are correlated by `req_id` and any additional
+/// keys configured (eg `trace_id`). A record holding several ids links them, so that the
+/// records of a request are grouped together even when some only hold its `req_id` and
+/// others only its `trace_id`. When an id is given, only the records linked to that id are
+/// returned. Otherwise, the records of every request are returned grouped by request.
+///
+pub struct RequestTracer {
+    id: Option<String>,
+    keys: Vec<String>,
+    records: Vec<TracedRecord>,
+    /// Node of each correlation id seen
+    nodes: HashMap<String, usize>,
+    /// Correlation id of each node, in the order they were first seen
+    ids: Vec<String>,
+    /// Parent of each node within the set of linked ids, a node being its own parent when
+    /// it is the root of its set
+    parents: Vec<usize>,
+}
+
+impl RequestTracer {
+    /// Creates a new tracer.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Correlation id to collect records for or `None` to collect all requests
+    /// * `keys` - Dotted paths of additional keys whose values correlate records
+    ///
+    pub fn new<I, S>(id: Option<String>, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut all_keys = vec![REQ_ID_KEY.to_string()];
+        for key in keys.into_iter().map(Into::into) {
+            if !all_keys.contains(&key) {
+                all_keys.push(key);
+            }
+        }
+
+        Self {
+            id,
+            keys: all_keys,
+            records: Vec::new(),
+            nodes: HashMap::new(),
+            ids: Vec::new(),
+            parents: Vec::new(),
+        }
+    }
+
+    /// Returns the distinct correlation ids held by the record. When additional keys are
+    /// configured, ids can be linked by records read later, so every record holding an id is
+    /// collected regardless of whether a single request is being traced. Otherwise, when a
+    /// single request is traced, no ids are returned for the records of other requests so
+    /// that they are never collected.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - Map containing the JSON data of the log record
+    ///
+    pub fn correlation_ids(&self, record: &Map<String, Value>) -> Vec<String> {
+        let mut ids: Vec<String> = Vec::new();
+
+        for value in self
+            .keys
+            .iter()
+            .filter_map(|key| lookup_path(record, key))
+            .filter(|value| !value.is_null())
+        {
+            let id = string_or_value!(value);
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+
+        match &self.id {
+            Some(id) if self.keys.len() == 1 && !ids.contains(id) => Vec::new(),
+            _ => ids,
+        }
+    }
+
+    /// Adds a record to the request of the passed ids, linking the ids to each other.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - Correlation ids returned by `correlation_ids`
+    /// * `time` - Timestamp of the record
+    /// * `record` - Map containing the JSON data of the log record
+    /// * `line` - Line of input the record was parsed from
+    /// * `line_no` - Line number of the input
+    ///
+    pub fn push(
+        &mut self,
+        ids: Vec<String>,
+        time: DateTime<Utc>,
+        record: &Map<String, Value>,
+        line: String,
+        line_no: usize,
+    ) {
+        let nodes: Vec<usize> = ids.into_iter().map(|id| self.node(id)).collect();
+        let first = match nodes.first() {
+            Some(first) => *first,
+            None => return,
+        };
+
+        for node in &nodes[1..] {
+            self.link(first, *node);
+        }
+
+        let mut traced = TracedRecord {
+            time,
+            line,
+            line_no,
+            sequence: self.records.len(),
+            node: first,
+            method: None,
+            url: None,
+            status_code: None,
+        };
+        traced.read_summary(record);
+        self.records.push(traced);
+    }
+
+    /// Returns the collected records grouped by request, with the traces ordered by the
+    /// time of their first record and the records of each trace in time order. A trace is
+    /// identified by the traced id or otherwise by the first of its ids that was read.
+    pub fn into_traces(mut self) -> Vec<Trace> {
+        let traced_root = match self.id.clone() {
+            Some(id) => match self.nodes.get(&id).copied() {
+                Some(node) => Some(self.root(node)),
+                None => return Vec::new(),
+            },
+            None => None,
+        };
+
+        let mut positions: HashMap<usize, usize> = HashMap::new();
+        let mut traces: Vec<Trace> = Vec::new();
+
+        for record in std::mem::take(&mut self.records) {
+            let root = self.root(record.node);
+            if traced_root.is_some_and(|traced_root| traced_root != root) {
+                continue;
+            }
+
+            let position = *positions.entry(root).or_insert_with(|| {
+                traces.push(Trace {
+                    id: self.id.clone().unwrap_or_else(|| self.ids[root].clone()),
+                    records: Vec::new(),
+                    method: None,
+                    url: None,
+                    status_code: None,
+                });
+                traces.len() - 1
+            });
+
+            let trace = &mut traces[position];
+            trace.update_summary(&record);
+            trace.records.push(record);
+        }
+
+        for trace in traces.iter_mut() {
+            trace
+                .records
+                .sort_by(|a, b| a.time.cmp(&b.time).then(a.sequence.cmp(&b.sequence)));
+        }
+        traces.sort_by_key(|trace| trace.records.first().map(|record| record.time));
+
+        traces
+    }
+
+    /// Returns the node of the passed correlation id, adding it if it hasn't been seen.
+    fn node(&mut self, id: String) -> usize {
+        if let Some(node) = self.nodes.get(&id) {
+            return *node;
+        }
+
+        let node = self.ids.len();
+        self.nodes.insert(id.clone(), node);
+        self.ids.push(id);
+        self.parents.push(node);
+        node
+    }
+
+    /// Returns the root of the set of linked ids containing the node.
+    fn root(&mut self, node: usize) -> usize {
+        let mut root = node;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // Point the nodes along the path directly at the root to keep later lookups short
+        let mut current = node;
+        while self.parents[current] != root {
+            let parent = self.parents[current];
+            self.parents[current] = root;
+            current = parent;
+        }
+
+        root
+    }
+
+    /// Links the sets of ids containing the passed nodes. The id seen first remains the
+    /// root, so that it identifies the trace.
+    fn link(&mut self, first: usize, second: usize) {
+        let (first, second) = (self.root(first), self.root(second));
+        if first != second {
+            self.parents[first.max(second)] = first.min(second);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(json: &str) -> Map<String, Value> {
+        serde_json::from_str(json).expect("invalid test JSON")
+    }
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(seconds, 0).unwrap()
+    }
+
+    fn push(tracer: &mut RequestTracer, seconds: i64, json: &str) {
+        let record = record(json);
+        let ids = tracer.correlation_ids(&record);
+        tracer.push(ids, at(seconds), &record, json.to_string(), 0);
+    }
+
+    #[test]
+    fn can_collect_single_request() {
+        let mut tracer = RequestTracer::new(Some("b".to_string()), Vec::<String>::new());
+        push(&mut tracer, 2, r#"{"req_id": "b", "msg": "second"}"#);
+        push(&mut tracer, 1, r#"{"req_id": "a", "msg": "other"}"#);
+        push(&mut tracer, 1, r#"{"req_id": "b", "msg": "first"}"#);
+        push(&mut tracer, 3, r#"{"msg": "no id"}"#);
+
+        let traces = tracer.into_traces();
+        assert_eq!(traces.len(), 1);
+        let lines: Vec<&str> = traces[0].records.iter().map(|r| r.line.as_str()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"req_id": "b", "msg": "first"}"#,
+                r#"{"req_id": "b", "msg": "second"}"#
+            ]
+        );
+    }
+
+    #[test]
+    fn only_collects_traced_request_without_additional_keys() {
+        let mut tracer = RequestTracer::new(Some("b".to_string()), Vec::<String>::new());
+        assert!(tracer
+            .correlation_ids(&record(r#"{"req_id": "a"}"#))
+            .is_empty());
+        assert_eq!(
+            tracer.correlation_ids(&record(r#"{"req_id": "b"}"#)),
+            vec!["b"]
+        );
+
+        push(&mut tracer, 1, r#"{"req_id": "a"}"#);
+        push(&mut tracer, 2, r#"{"req_id": "b"}"#);
+        assert_eq!(tracer.records.len(), 1);
+
+        let tracer = RequestTracer::new(Some("b".to_string()), vec!["trace_id"]);
+        assert_eq!(
+            tracer.correlation_ids(&record(r#"{"req_id": "a"}"#)),
+            vec!["a"]
+        );
+    }
+
+    #[test]
+    fn can_group_all_requests() {
+        let mut tracer = RequestTracer::new(None, Vec::<String>::new());
+        push(&mut tracer, 5, r#"{"req_id": "late"}"#);
+        push(&mut tracer, 1, r#"{"req_id": "early"}"#);
+        push(&mut tracer, 2, r#"{"req_id": 7}"#);
+
+        let ids: Vec<String> = tracer.into_traces().into_iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec!["early", "7", "late"]);
+    }
+
+    #[test]
+    fn can_correlate_by_additional_keys() {
+        let mut tracer = RequestTracer::new(Some("abc".to_string()), vec!["trace_id", "ctx.id"]);
+        push(&mut tracer, 1, r#"{"trace_id": "abc"}"#);
+        push(&mut tracer, 2, r#"{"ctx": {"id": "abc"}}"#);
+        push(&mut tracer, 3, r#"{"req_id": "xyz", "trace_id": "abc"}"#);
+
+        assert_eq!(tracer.into_traces()[0].records.len(), 3);
+    }
+
+    #[test]
+    fn can_link_ids_transitively() {
+        for id in [None, Some("abc".to_string())] {
+            let mut tracer = RequestTracer::new(id, vec!["trace_id"]);
+            push(&mut tracer, 1, r#"{"req_id": "xyz", "msg": "before link"}"#);
+            push(&mut tracer, 2, r#"{"trace_id": "abc", "msg": "upstream"}"#);
+            push(&mut tracer, 3, r#"{"req_id": "xyz", "trace_id": "abc"}"#);
+            push(&mut tracer, 4, r#"{"req_id": "other"}"#);
+
+            let traces = tracer.into_traces();
+            assert_eq!(traces[0].records.len(), 3);
+            assert_eq!(traces.len(), if traces[0].id == "abc" { 1 } else { 2 });
+        }
+    }
+
+    #[test]
+    fn can_summarize_request() {
+        let mut tracer = RequestTracer::new(None, Vec::<String>::new());
+        push(
+            &mut tracer,
+            1,
+            r#"{"req_id": "a", "req": {"method": "GET", "url": "/users/42"}}"#,
+        );
+        push(
+            &mut tracer,
+            2,
+            r#"{"req_id": "a", "res": {"statusCode": 404}}"#,
+        );
+
+        assert_eq!(
+            tracer.into_traces()[0].summary(),
+            "a: GET /users/42 -> 404 (2 records over 1000ms)"
+        );
+    }
+}
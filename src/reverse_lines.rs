@@ -0,0 +1,152 @@
+use std::io::{Read, Result, Seek, SeekFrom};
+
+/// Number of bytes read from the end of the input at a time
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Iterator over the lines of a seekable input starting from the last line and moving
+/// towards the first. Each line is returned along with the byte offset at which it starts,
+/// so that the input can later be read forwards from that position. Only the chunks of the
+/// input containing the lines iterated over are read.
+///
+pub struct ReverseLines<R> {
+    reader: R,
+    /// Offset of the start of the data held in the buffer
+    position: u64,
+    /// Data that has been read but not yet returned as lines
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl<R: Read + Seek> ReverseLines<R> {
+    /// Creates a new iterator positioned at the end of the passed reader.
+    ///
+    /// # Errors
+    ///
+    /// If the reader can't be seeked or read, an IO error is returned.
+    ///
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut end = reader.seek(SeekFrom::End(0))?;
+
+        // A newline at the end of the input terminates the last line rather than starting
+        // an empty one
+        if end > 0 {
+            let mut last = [0u8; 1];
+            reader.seek(SeekFrom::Start(end - 1))?;
+            reader.read_exact(&mut last)?;
+            if last[0] == b'\n' {
+                end -= 1;
+            }
+        }
+
+        Ok(Self {
+            reader,
+            position: end,
+            buffer: Vec::new(),
+            done: end == 0,
+        })
+    }
+
+    /// Reads the chunk of input preceding the data held in the buffer.
+    fn read_previous_chunk(&mut self) -> Result<()> {
+        let size = CHUNK_SIZE.min(self.position);
+        self.position -= size;
+
+        let mut chunk = vec![0u8; size as usize];
+        self.reader.seek(SeekFrom::Start(self.position))?;
+        self.reader.read_exact(&mut chunk)?;
+
+        chunk.append(&mut self.buffer);
+        self.buffer = chunk;
+
+        Ok(())
+    }
+}
+
+/// Converts the raw bytes of a line to text without its line terminator.
+fn to_line(mut bytes: Vec<u8>) -> String {
+    if bytes.last() == Some(&b'\r') {
+        bytes.pop();
+    }
+
+    match String::from_utf8(bytes) {
+        Ok(line) => line,
+        Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+    }
+}
+
+impl<R: Read + Seek> Iterator for ReverseLines<R> {
+    type Item = Result<(u64, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.done {
+                return None;
+            }
+
+            if let Some(newline_pos) = self.buffer.iter().rposition(|byte| *byte == b'\n') {
+                let line = self.buffer.split_off(newline_pos + 1);
+                self.buffer.pop();
+                let offset = self.position + newline_pos as u64 + 1;
+                return Some(Ok((offset, to_line(line))));
+            }
+
+            // The remaining data is the first line of the input
+            if self.position == 0 {
+                self.done = true;
+                let line = std::mem::take(&mut self.buffer);
+                return Some(Ok((0, to_line(line))));
+            }
+
+            if let Err(e) = self.read_previous_chunk() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn reverse_lines(input: &str) -> Vec<(u64, String)> {
+        ReverseLines::new(Cursor::new(input.as_bytes().to_vec()))
+            .unwrap()
+            .map(|line| line.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn can_read_lines_in_reverse() {
+        assert_eq!(
+            reverse_lines("one\ntwo\r\n\nthree\n"),
+            vec![
+                (10, "three".to_string()),
+                (9, "".to_string()),
+                (4, "two".to_string()),
+                (0, "one".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn can_read_input_without_trailing_newline() {
+        assert_eq!(
+            reverse_lines("one\ntwo"),
+            vec![(4, "two".to_string()), (0, "one".to_string())]
+        );
+        assert!(reverse_lines("").is_empty());
+    }
+
+    #[test]
+    fn can_read_lines_spanning_chunks() {
+        let long_line = "x".repeat(CHUNK_SIZE as usize + 10);
+        let input = format!("first\n{long_line}\nlast\n");
+        let lines = reverse_lines(&input);
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], (6, long_line));
+        assert_eq!(lines[2], (0, "first".to_string()));
+    }
+}
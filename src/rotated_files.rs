@@ -0,0 +1,135 @@
+use std::fs;
+use std::io::Result as IoResult;
+use std::path::{Path, PathBuf};
+
+/// Returns the passed log file preceded by its rotated siblings (eg `app.log.0`,
+/// `app.log.1.gz`) as produced by bunyan's rotating-file stream and by logrotate. Both
+/// number rotated copies so that a higher number is older, so the siblings are ordered
+/// from the highest number to the lowest followed by the file itself. This allows the
+/// family to be read as one continuous history.
+///
+/// # Arguments
+///
+/// * `path` - Path of the current log file
+///
+/// # Errors
+///
+/// If the directory containing the file can't be read, an IO error is returned.
+///
+pub fn rotated_family<P: AsRef<Path>>(path: P) -> IoResult<Vec<PathBuf>> {
+    let path = path.as_ref();
+    let file_name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(file_name) => file_name,
+        None => return Ok(vec![path.to_path_buf()]),
+    };
+    let dir = match path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        Some(parent) => parent,
+        None => Path::new("."),
+    };
+
+    let mut siblings: Vec<(u64, PathBuf)> = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let generation = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(file_name))
+            .and_then(|suffix| suffix.strip_prefix('.'))
+            .and_then(rotation_number);
+
+        if let Some(generation) = generation {
+            siblings.push((generation, path.with_file_name(name)));
+        }
+    }
+
+    siblings.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    let mut family: Vec<PathBuf> = siblings.into_iter().map(|(_, path)| path).collect();
+    family.push(path.to_path_buf());
+
+    Ok(family)
+}
+
+/// Returns the number of a rotated copy from the suffix following the file name (eg `1`
+/// or `1.gz`).
+fn rotation_number(suffix: &str) -> Option<u64> {
+    let number = suffix.strip_suffix(".gz").unwrap_or(suffix);
+
+    if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) {
+        number.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Returns the name of the file a rotated copy was made from by removing its rotation
+/// suffix (eg `app.log` for `app.log.1.gz`). Other names are returned unchanged.
+pub(crate) fn unrotated_name(name: &str) -> &str {
+    name.rmatch_indices('.')
+        .take(2)
+        .find(|(pos, _)| rotation_number(&name[pos + 1..]).is_some())
+        .map_or(name, |(pos, _)| &name[..pos])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_order_rotated_siblings_oldest_first() {
+        let dir = std::env::temp_dir().join(format!("bunyan-rotated-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        for name in [
+            "app.log",
+            "app.log.0",
+            "app.log.1.gz",
+            "app.log.10.gz",
+            "app.log.2",
+            "app.log.bvidx",
+            "app.log.old",
+            "other.log.1",
+        ] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let names: Vec<String> = rotated_family(dir.join("app.log"))
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "app.log.10.gz",
+                "app.log.2",
+                "app.log.1.gz",
+                "app.log.0",
+                "app.log"
+            ]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn can_parse_rotation_numbers() {
+        assert_eq!(rotation_number("0"), Some(0));
+        assert_eq!(rotation_number("12.gz"), Some(12));
+        assert_eq!(rotation_number("gz"), None);
+        assert_eq!(rotation_number("1.bak"), None);
+    }
+
+    #[test]
+    fn can_remove_rotation_suffix() {
+        assert_eq!(unrotated_name("/var/log/app.log.1.gz"), "/var/log/app.log");
+        assert_eq!(unrotated_name("/var/log/app.log.0"), "/var/log/app.log");
+        assert_eq!(unrotated_name("/var/log/app.log"), "/var/log/app.log");
+        assert_eq!(unrotated_name("/var/log/app.log.gz"), "/var/log/app.log.gz");
+    }
+}
@@ -0,0 +1,647 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufReader, Read, Result as IoResult, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+
+use crate::errors::StateFileError;
+use crate::record_throttle::fnv1a_hash;
+use crate::rotated_files::{rotated_family, unrotated_name};
+
+/// Number of bytes read at a time when searching backwards for the end of the last line
+const SEARCH_CHUNK_SIZE: u64 = 4096;
+/// Number of bytes preceding the checkpoint that are hashed to detect that the file was
+/// truncated and rewritten past the checkpoint since it was last read
+const FINGERPRINT_SIZE: u64 = 256;
+
+/// Position up to which a file has been read
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Checkpoint {
+    inode: u64,
+    /// Offset read up to, which for compressed files is the length of the compressed file
+    offset: u64,
+    /// Hash of the bytes preceding the offset, which compressed files don't have because
+    /// they are only ever read in full
+    fingerprint: Option<u64>,
+}
+
+/// A range of bytes of a file to read. The offsets of compressed files are offsets within
+/// their decompressed contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadSegment {
+    pub path: PathBuf,
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Checkpoints recording how far each file has been read, which are persisted between
+/// invocations so that only the records added since the last run are displayed in the
+/// manner of `logtail`. Files are identified by inode as well as path, so that when a file
+/// is rotated the remainder of the rotated copy is read before the new file.
+///
+pub struct ReadState {
+    path: PathBuf,
+    checkpoints: BTreeMap<String, Checkpoint>,
+}
+
+impl ReadState {
+    /// Loads the checkpoints from the passed state file. A state file that doesn't exist
+    /// yet is treated as empty.
+    ///
+    /// # Errors
+    ///
+    /// If the state file can't be read or parsed, or the checkpoint of an uncompressed file
+    /// has no fingerprint, a `StateFileError` is returned.
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ReadState, StateFileError> {
+        let path = path.as_ref().to_path_buf();
+
+        let checkpoints: BTreeMap<String, Checkpoint> = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| StateFileError::new(path.to_string_lossy(), e.to_string()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(StateFileError::new(path.to_string_lossy(), e.to_string())),
+        };
+
+        if let Some(key) = checkpoints
+            .iter()
+            .find(|(key, checkpoint)| checkpoint.fingerprint.is_none() && !is_compressed(key))
+            .map(|(key, _)| key)
+        {
+            return Err(StateFileError::new(
+                path.to_string_lossy(),
+                format!("the checkpoint of [{key}] has no fingerprint"),
+            ));
+        }
+
+        Ok(ReadState { path, checkpoints })
+    }
+
+    /// Writes the checkpoints to the state file, creating its directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// If the state file can't be written, a `StateFileError` is returned.
+    ///
+    pub fn save(&self) -> Result<(), StateFileError> {
+        let error =
+            |e: std::io::Error| StateFileError::new(self.path.to_string_lossy(), e.to_string());
+
+        if let Some(parent) = self
+            .path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent).map_err(error)?;
+        }
+
+        let contents = serde_json::to_string_pretty(&self.checkpoints)
+            .map_err(|e| StateFileError::new(self.path.to_string_lossy(), e.to_string()))?;
+
+        // Write to a temporary file first so that an interrupted write can't corrupt the state
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        fs::write(&temp_path, contents).map_err(error)?;
+        fs::rename(&temp_path, &self.path).map_err(error)
+    }
+
+    /// Returns the ranges of bytes added to the file since it was last read and moves its
+    /// checkpoint to the end of the last complete line. When the file has been rotated, the
+    /// remainder of the rotated copy is returned before the new file. When the file has been
+    /// truncated, it is read from the start. This includes a file that was truncated and has
+    /// since grown past the checkpoint (eg by `copytruncate` rotation), which is detected by
+    /// the bytes preceding the checkpoint no longer matching those that were read. A rotated
+    /// copy that was read under the name of the file it was rotated from is read from that
+    /// file's checkpoint, which it takes over, so that reading the rotated copies of a file
+    /// along with the file itself doesn't display records again.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - Path of the file to read
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be read, an IO error is returned.
+    ///
+    pub fn unread_segments(&mut self, filename: &str) -> IoResult<Vec<ReadSegment>> {
+        let path = PathBuf::from(filename);
+        let metadata = fs::metadata(&path)?;
+        let inode = inode(&metadata);
+        let key = checkpoint_key(&path);
+
+        // Compressed files aren't appended to, so they are either read in full or skipped
+        // when unchanged
+        if is_compressed(filename) {
+            let length = metadata.len();
+            let is_unchanged = self
+                .checkpoints
+                .get(&key)
+                .is_some_and(|checkpoint| checkpoint.inode == inode && checkpoint.offset == length);
+
+            let segment = if is_unchanged {
+                None
+            } else {
+                match self.take_moved(&path, &key, &metadata)? {
+                    Some(moved) => Some(moved),
+                    None => Some(ReadSegment {
+                        path: path.clone(),
+                        start: 0,
+                        end: scan_compressed(&path, 0)?.0,
+                    }),
+                }
+            };
+
+            self.checkpoints.insert(
+                key,
+                Checkpoint {
+                    inode,
+                    offset: length,
+                    fingerprint: None,
+                },
+            );
+
+            return Ok(segment
+                .filter(|segment| segment.start < segment.end)
+                .into_iter()
+                .collect());
+        }
+
+        let end = complete_length(&path, metadata.len())?;
+        let mut segments = Vec::new();
+        let own_checkpoint = self
+            .checkpoints
+            .get(&key)
+            .filter(|checkpoint| checkpoint.inode == inode && checkpoint.offset <= end)
+            .cloned();
+        let start = match own_checkpoint {
+            Some(checkpoint) => {
+                let is_rewritten =
+                    Some(fingerprint(&path, checkpoint.offset)?) != checkpoint.fingerprint;
+
+                if is_rewritten {
+                    // The file may have been rotated and a new file given the same inode, or
+                    // copied elsewhere before being truncated
+                    if let Some(rotated) = find_rotated(&path, &checkpoint)?
+                        .filter(|rotated| rotated.start < rotated.end)
+                    {
+                        segments.push(rotated);
+                    }
+                    0
+                } else {
+                    checkpoint.offset
+                }
+            }
+            None => match self.take_moved(&path, &key, &metadata)? {
+                Some(moved) => moved.start,
+                None => match self.checkpoints.get(&key) {
+                    Some(checkpoint) if checkpoint.inode != inode => {
+                        match find_rotated(&path, checkpoint)? {
+                            Some(rotated) if rotated.start < rotated.end => segments.push(rotated),
+                            Some(_) => {}
+                            None => eprintln!(
+                                "warning: the rotated copy of {filename} can't be found, so the \
+                                 records added to it before it was rotated are skipped"
+                            ),
+                        }
+                        0
+                    }
+                    _ => 0,
+                },
+            },
+        };
+
+        let checkpoint = Checkpoint {
+            inode,
+            offset: end,
+            fingerprint: Some(fingerprint(&path, end)?),
+        };
+
+        if start < end {
+            segments.push(ReadSegment { path, start, end });
+        }
+
+        self.checkpoints.insert(key, checkpoint);
+
+        Ok(segments)
+    }
+
+    /// Moves the checkpoint recorded for a file under another name of the same rotated file
+    /// (eg `app.log` for `app.log.1`) to this file if the file is the one it was recorded
+    /// for, as happens when the file has been renamed or compressed by rotation since it was
+    /// last read. This keeps records from being read again when the rotated copies of a file
+    /// are read as well as the file itself. The segment of the file following the checkpoint
+    /// is returned, which is empty for a compressed file that has only been renamed.
+    fn take_moved(
+        &mut self,
+        path: &Path,
+        key: &str,
+        metadata: &Metadata,
+    ) -> IoResult<Option<ReadSegment>> {
+        let unrotated = unrotated_name(key);
+        let candidates: Vec<String> = self
+            .checkpoints
+            .keys()
+            .filter(|other| *other != key && unrotated_name(other) == unrotated)
+            .cloned()
+            .collect();
+
+        for other in candidates {
+            let checkpoint = &self.checkpoints[&other];
+
+            let moved = match checkpoint.fingerprint {
+                Some(_) => resume_segment(path, checkpoint)?,
+                // A compressed file keeps its inode and length when renamed
+                None if is_compressed(key)
+                    && checkpoint.inode == inode(metadata)
+                    && checkpoint.offset == metadata.len() =>
+                {
+                    Some(ReadSegment {
+                        path: path.to_path_buf(),
+                        start: 0,
+                        end: 0,
+                    })
+                }
+                None => None,
+            };
+
+            if moved.is_some() {
+                self.checkpoints.remove(&other);
+                return Ok(moved);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Moves the checkpoint of the file to the end of its last complete line without
+    /// returning what was added, so that a later run only displays newer records.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - Path of the file that has been read in full
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be read, an IO error is returned.
+    ///
+    pub fn mark_read(&mut self, filename: &str) -> IoResult<()> {
+        self.unread_segments(filename).map(|_| ())
+    }
+}
+
+/// Returns the key checkpoints are stored under, which is the absolute path of the file
+/// when it can be determined.
+fn checkpoint_key(path: &Path) -> String {
+    fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(unix)]
+pub(crate) fn inode(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn inode(_metadata: &Metadata) -> u64 {
+    0
+}
+
+/// Returns true if the file is compressed, judging by its name.
+fn is_compressed(filename: &str) -> bool {
+    filename.ends_with(".gz")
+}
+
+/// Returns the segment added after the checkpoint to the rotated copy of the file, which is
+/// searched for amongst all of its rotated siblings (eg `app.log.0`, `app.log.2.gz`),
+/// starting with the newest. `None` is returned when no copy can be found.
+fn find_rotated(path: &Path, checkpoint: &Checkpoint) -> IoResult<Option<ReadSegment>> {
+    let family = rotated_family(path)?;
+    let siblings = &family[..family.len() - 1];
+
+    for sibling in siblings.iter().rev() {
+        if let Some(segment) = resume_segment(sibling, checkpoint)? {
+            return Ok(Some(segment));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the segment of the file following the checkpoint if the file is the one the
+/// checkpoint was recorded for under another name. Uncompressed files are identified by
+/// the checkpoint's inode. As compressing a file gives it a new inode, compressed files
+/// are identified by the bytes of their decompressed contents preceding the checkpoint.
+fn resume_segment(path: &Path, checkpoint: &Checkpoint) -> IoResult<Option<ReadSegment>> {
+    let filename = path.to_string_lossy();
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let (end, hash) = if is_compressed(&filename) {
+        scan_compressed(path, checkpoint.offset)?
+    } else if inode(&metadata) == checkpoint.inode {
+        let end = complete_length(path, metadata.len())?;
+        let hash = if checkpoint.offset <= end {
+            Some(fingerprint(path, checkpoint.offset)?)
+        } else {
+            None
+        };
+        (end, hash)
+    } else {
+        return Ok(None);
+    };
+
+    if hash.is_none() || hash != checkpoint.fingerprint {
+        return Ok(None);
+    }
+
+    Ok(Some(ReadSegment {
+        path: path.to_path_buf(),
+        start: checkpoint.offset,
+        end,
+    }))
+}
+
+/// Returns the length of the decompressed contents of a compressed file along with a hash
+/// of the bytes preceding the passed offset within them, when the contents reach the offset.
+fn scan_compressed(path: &Path, offset: u64) -> IoResult<(u64, Option<u64>)> {
+    let mut reader = GzDecoder::new(BufReader::new(File::open(path)?));
+    let size = FINGERPRINT_SIZE.min(offset);
+
+    let skipped = io::copy(&mut reader.by_ref().take(offset - size), &mut io::sink())?;
+    let mut bytes = Vec::with_capacity(size as usize);
+    reader.by_ref().take(size).read_to_end(&mut bytes)?;
+    let remaining = io::copy(&mut reader, &mut io::sink())?;
+
+    let length = skipped + bytes.len() as u64 + remaining;
+    let hash = if length >= offset {
+        Some(fnv1a_hash(&bytes))
+    } else {
+        None
+    };
+
+    Ok((length, hash))
+}
+
+/// Returns a hash of the bytes of the file preceding the passed offset.
+pub(crate) fn fingerprint(path: &Path, offset: u64) -> IoResult<u64> {
+    let size = FINGERPRINT_SIZE.min(offset);
+    let mut bytes = vec![0u8; size as usize];
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset - size))?;
+    file.read_exact(&mut bytes)?;
+
+    Ok(fnv1a_hash(&bytes))
+}
+
+/// Returns the length of the file up to and including its last newline, so that a line
+/// that is still being written isn't split between runs.
+pub(crate) fn complete_length(path: &Path, length: u64) -> IoResult<u64> {
+    let mut file = File::open(path)?;
+    let mut end = length;
+
+    while end > 0 {
+        let size = SEARCH_CHUNK_SIZE.min(end);
+        let mut chunk = vec![0u8; size as usize];
+        file.seek(SeekFrom::Start(end - size))?;
+        file.read_exact(&mut chunk)?;
+
+        if let Some(newline_pos) = chunk.iter().rposition(|byte| *byte == b'\n') {
+            return Ok(end - size + newline_pos as u64 + 1);
+        }
+
+        end -= size;
+    }
+
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("bunyan-state-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn append(path: &Path, text: &str) {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    fn ranges(segments: Vec<ReadSegment>) -> Vec<(u64, u64)> {
+        segments.iter().map(|s| (s.start, s.end)).collect()
+    }
+
+    #[test]
+    fn can_read_only_new_lines() {
+        let dir = temp_dir("new-lines");
+        let log = dir.join("app.log");
+        let log_name = log.to_str().unwrap();
+        let state_path = dir.join("state");
+
+        append(&log, "one\ntwo\npartial");
+        let mut state = ReadState::load(&state_path).unwrap();
+        assert_eq!(
+            ranges(state.unread_segments(log_name).unwrap()),
+            vec![(0, 8)]
+        );
+        state.save().unwrap();
+
+        append(&log, " line\nthree\n");
+        let mut state = ReadState::load(&state_path).unwrap();
+        assert_eq!(
+            ranges(state.unread_segments(log_name).unwrap()),
+            vec![(8, 27)]
+        );
+        assert!(state.unread_segments(log_name).unwrap().is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn can_read_remainder_of_rotated_file() {
+        let dir = temp_dir("rotated");
+        let log = dir.join("app.log");
+        let log_name = log.to_str().unwrap();
+        let mut state = ReadState::load(dir.join("state")).unwrap();
+
+        append(&log, "one\n");
+        state.unread_segments(log_name).unwrap();
+        append(&log, "two\n");
+        fs::rename(&log, dir.join("app.log.0")).unwrap();
+        append(&log, "three\n");
+
+        let segments = state.unread_segments(log_name).unwrap();
+        assert_eq!(segments[0].path, dir.join("app.log.0"));
+        assert_eq!(ranges(segments), vec![(4, 8), (0, 6)]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn can_read_remainder_of_file_rotated_past_first_copies() {
+        let dir = temp_dir("rotated-later");
+        let log = dir.join("app.log");
+        let log_name = log.to_str().unwrap();
+        let mut state = ReadState::load(dir.join("state")).unwrap();
+
+        append(&log, "one\n");
+        state.unread_segments(log_name).unwrap();
+        append(&log, "two\n");
+        append(&dir.join("app.log.0"), "newer\n");
+        fs::rename(&log, dir.join("app.log.2")).unwrap();
+        append(&log, "three\n");
+
+        let segments = state.unread_segments(log_name).unwrap();
+        assert_eq!(segments[0].path, dir.join("app.log.2"));
+        assert_eq!(ranges(segments), vec![(4, 8), (0, 6)]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn can_read_remainder_of_file_rotated_and_compressed() {
+        let dir = temp_dir("rotated-compressed");
+        let log = dir.join("app.log");
+        let log_name = log.to_str().unwrap();
+        let mut state = ReadState::load(dir.join("state")).unwrap();
+
+        append(&log, "one\n");
+        state.unread_segments(log_name).unwrap();
+        append(&log, "two\n");
+
+        let mut encoder = GzEncoder::new(
+            File::create(dir.join("app.log.1.gz")).unwrap(),
+            Compression::default(),
+        );
+        encoder.write_all(&fs::read(&log).unwrap()).unwrap();
+        encoder.finish().unwrap();
+        fs::remove_file(&log).unwrap();
+        append(&log, "three\n");
+
+        let segments = state.unread_segments(log_name).unwrap();
+        assert_eq!(segments[0].path, dir.join("app.log.1.gz"));
+        assert_eq!(ranges(segments), vec![(4, 8), (0, 6)]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rotated_copies_read_under_another_name_are_not_read_again() {
+        let dir = temp_dir("rotated-family");
+        let log = dir.join("app.log");
+        let rotated = dir.join("app.log.1");
+        let compressed = dir.join("app.log.2.gz");
+        let mut state = ReadState::load(dir.join("state")).unwrap();
+
+        append(&log, "one\n");
+        state.unread_segments(log.to_str().unwrap()).unwrap();
+        append(&log, "two\n");
+        fs::rename(&log, &rotated).unwrap();
+        append(&log, "three\n");
+
+        let mut read = Vec::new();
+        for path in [&rotated, &log] {
+            let segments = state.unread_segments(path.to_str().unwrap()).unwrap();
+            read.extend(segments.into_iter().map(|s| (s.path, s.start, s.end)));
+        }
+        assert_eq!(read, vec![(rotated.clone(), 4, 8), (log.clone(), 0, 6)]);
+
+        // Shift the copies again, compressing the oldest
+        let mut encoder =
+            GzEncoder::new(File::create(&compressed).unwrap(), Compression::default());
+        encoder.write_all(&fs::read(&rotated).unwrap()).unwrap();
+        encoder.finish().unwrap();
+        fs::rename(&log, &rotated).unwrap();
+        append(&log, "four\n");
+
+        let mut read = Vec::new();
+        for path in [&compressed, &rotated, &log] {
+            let segments = state.unread_segments(path.to_str().unwrap()).unwrap();
+            read.extend(segments.into_iter().map(|s| (s.path, s.start, s.end)));
+        }
+        assert_eq!(read, vec![(log, 0, 5)]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn truncated_file_is_read_from_start() {
+        let dir = temp_dir("truncated");
+        let log = dir.join("app.log");
+        let log_name = log.to_str().unwrap();
+        let mut state = ReadState::load(dir.join("state")).unwrap();
+
+        append(&log, "one\ntwo\n");
+        state.unread_segments(log_name).unwrap();
+        fs::write(&log, "new\n").unwrap();
+
+        assert_eq!(
+            ranges(state.unread_segments(log_name).unwrap()),
+            vec![(0, 4)]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn truncated_file_that_grew_past_checkpoint_is_read_from_start() {
+        let dir = temp_dir("copytruncate");
+        let log = dir.join("app.log");
+        let log_name = log.to_str().unwrap();
+        let mut state = ReadState::load(dir.join("state")).unwrap();
+
+        append(&log, "one\ntwo\n");
+        state.unread_segments(log_name).unwrap();
+        fs::write(&log, "three\nfour\n").unwrap();
+
+        assert_eq!(
+            ranges(state.unread_segments(log_name).unwrap()),
+            vec![(0, 11)]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn checkpoint_of_uncompressed_file_requires_fingerprint() {
+        let dir = temp_dir("no-fingerprint");
+        let state_path = dir.join("state");
+
+        fs::write(
+            &state_path,
+            r#"{"/var/log/app.log": {"inode": 1, "offset": 4}}"#,
+        )
+        .unwrap();
+        assert!(ReadState::load(&state_path).is_err());
+
+        let contents =
+            r#"{"/var/log/app.log.1.gz": {"inode": 1, "offset": 4, "fingerprint": null}}"#;
+        fs::write(&state_path, contents).unwrap();
+        assert!(ReadState::load(&state_path).is_ok());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,167 @@
+use std::io::Write;
+
+use chrono::{Local, SecondsFormat};
+
+use crate::logfmt_logger::flatten;
+use crate::{BunyanLine, LoggerOutputConfig};
+
+/// Syslog facility of the messages (user-level messages)
+const FACILITY: u8 = 1;
+/// ID of the structured data element holding the extra fields of a record. 32473 is the
+/// private enterprise number reserved for documentation by RFC 5612.
+const SD_ID: &str = "bunyan@32473";
+/// Maximum lengths of the header fields and structured data parameter names in RFC 5424
+const MAX_APP_NAME: usize = 48;
+const MAX_PROCID: usize = 128;
+const MAX_MSGID: usize = 32;
+const MAX_SD_NAME: usize = 32;
+const MAX_HOSTNAME: usize = 255;
+
+/// Returns the syslog severity of a Bunyan level. Fatal records are considered critical
+/// rather than an emergency as they concern a single process. Custom levels are given the
+/// severity of the named level below them.
+///
+/// # Arguments
+///
+/// * `level` - Numeric Bunyan level
+///
+pub(crate) fn syslog_severity(level: u16) -> u8 {
+    match level {
+        0..=29 => 7,
+        30..=39 => 6,
+        40..=49 => 4,
+        50..=59 => 3,
+        _ => 2,
+    }
+}
+
+/// Writes a log record as a RFC 5424 syslog line in the form of
+/// `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID [SD] MSG`. The name of the record is
+/// used as the app name, the pid as the process id and the component as the message id.
+/// The remaining fields are written as the parameters of a single structured data element,
+/// with nested objects flattened into dotted names.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `log` - Log record to write
+/// * `output_config` - Configuration containing the time zone to display
+///
+pub fn write_syslog_line<W: Write>(
+    writer: &mut W,
+    log: &BunyanLine,
+    output_config: &LoggerOutputConfig,
+) {
+    let priority = FACILITY * 8 + syslog_severity(log.level);
+    let time = if output_config.display_local_time {
+        log.time
+            .with_timezone(&Local)
+            .to_rfc3339_opts(SecondsFormat::Millis, true)
+    } else {
+        log.time.to_rfc3339_opts(SecondsFormat::Millis, true)
+    };
+
+    let mut fields = Vec::new();
+    flatten(&log.other, "", &mut fields);
+
+    let structured_data = if fields.is_empty() {
+        "-".to_string()
+    } else {
+        let params: Vec<String> = fields
+            .iter()
+            .map(|(key, value)| {
+                format!(
+                    "{}=\"{}\"",
+                    header_field(key, MAX_SD_NAME, &['=', ']', '"']),
+                    escape_param_value(&string_or_value!(value))
+                )
+            })
+            .collect();
+        format!("[{} {}]", SD_ID, params.join(" "))
+    };
+
+    let pid = log.pid.to_string();
+    let msg_id = log.component.as_deref().unwrap_or_default();
+
+    wln!(
+        writer,
+        "<{}>1 {} {} {} {} {} {} {}",
+        priority,
+        time,
+        header_field(&log.hostname, MAX_HOSTNAME, &[]),
+        header_field(&log.name, MAX_APP_NAME, &[]),
+        header_field(&pid, MAX_PROCID, &[]),
+        header_field(msg_id, MAX_MSGID, &[]),
+        structured_data,
+        escape_line_breaks(&log.msg)
+    );
+}
+
+/// Returns the value as a header field or parameter name, which may only contain printable
+/// ASCII characters other than space and the passed characters and may not be longer than
+/// the passed length. Other characters are replaced with `_` and an empty value is written
+/// as `-`.
+fn header_field(value: &str, max_len: usize, excluded: &[char]) -> String {
+    if value.is_empty() {
+        return "-".to_string();
+    }
+
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_graphic() && !excluded.contains(&c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(max_len)
+        .collect()
+}
+
+/// Returns the value escaped for a structured data parameter, in which `"`, `\` and `]`
+/// must be preceded by a backslash. Line breaks are escaped so that each record remains on
+/// a single line.
+fn escape_param_value(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(']', "\\]");
+
+    escape_line_breaks(&escaped)
+}
+
+/// Returns the text with its line breaks written as `\n` and `\r`.
+fn escape_line_breaks(text: &str) -> String {
+    text.replace('\r', "\\r").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_map_levels_to_severities() {
+        let severities: Vec<u8> = [10, 20, 30, 40, 50, 60, 35]
+            .iter()
+            .map(|level| syslog_severity(*level))
+            .collect();
+
+        assert_eq!(severities, vec![7, 7, 6, 4, 3, 2, 6]);
+    }
+
+    #[test]
+    fn can_escape_header_fields_and_params() {
+        assert_eq!(header_field("my app", MAX_APP_NAME, &[]), "my_app");
+        assert_eq!(header_field("", MAX_MSGID, &[]), "-");
+        assert_eq!(
+            header_field("req.headers.x=y", MAX_SD_NAME, &['=', ']', '"']),
+            "req.headers.x_y"
+        );
+        assert_eq!(header_field(&"a".repeat(40), MAX_SD_NAME, &[]).len(), 32);
+        assert_eq!(
+            escape_param_value("say \"hi\" [a]\\\nbye"),
+            "say \\\"hi\\\" [a\\]\\\\\\nbye"
+        );
+    }
+}
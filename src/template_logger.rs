@@ -0,0 +1,477 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::str::FromStr;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Local, SecondsFormat};
+use colored::*;
+use serde_json::Value;
+
+use crate::errors::TemplateParseError;
+use crate::field_filter::lookup_path;
+use crate::formatting_logger::level_color;
+use crate::{BunyanLine, LogLevel, LoggerOutputConfig};
+
+/// Template used by `-o template` when no template is specified
+pub const DEFAULT_TEMPLATE: &str =
+    "{time:%H:%M:%S} {level:>5|level} [{name}/{component?}] {msg} {*}";
+/// Placeholder replaced by the fields of the record not referenced elsewhere in the template
+const EXTRAS_PLACEHOLDER: &str = "*";
+/// Text written in place of a field that is missing from a record and isn't optional
+const MISSING_VALUE: &str = "-";
+/// Fields that are always present in a record and therefore never part of the extras
+const CORE_FIELDS: [&str; 8] = [
+    "name",
+    "hostname",
+    "pid",
+    "component",
+    "level",
+    "msg",
+    "time",
+    "v",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alignment {
+    Left,
+    Right,
+    Center,
+}
+
+/// Styling applied to the text of a placeholder
+#[derive(Debug, Clone)]
+enum Style {
+    Color(Color),
+    Bold,
+    Dimmed,
+    Italic,
+    Underline,
+    /// Colors the text by the level of the record in the same manner as the long format
+    Level,
+}
+
+impl Style {
+    fn parse(template: &str, name: &str) -> Result<Style, TemplateParseError> {
+        match name.trim() {
+            "bold" => Ok(Style::Bold),
+            "dimmed" | "dim" => Ok(Style::Dimmed),
+            "italic" => Ok(Style::Italic),
+            "underline" => Ok(Style::Underline),
+            "level" => Ok(Style::Level),
+            color => Color::from_str(color)
+                .map(Style::Color)
+                .map_err(|_| TemplateParseError::new(template, format!("unknown style [{color}]"))),
+        }
+    }
+
+    fn apply(&self, text: ColoredString, level: &LogLevel) -> ColoredString {
+        match self {
+            Style::Color(color) => text.color(*color),
+            Style::Bold => text.bold(),
+            Style::Dimmed => text.dimmed(),
+            Style::Italic => text.italic(),
+            Style::Underline => text.underline(),
+            Style::Level => level_color(level, text),
+        }
+    }
+}
+
+/// A `{...}` placeholder within a template
+#[derive(Debug, Clone)]
+struct Placeholder {
+    /// Dotted path of the field or `*` for the extra fields
+    path: String,
+    /// When set, nothing is written for a missing field rather than `-`
+    is_optional: bool,
+    /// strftime format of the `time` field
+    time_format: Option<String>,
+    alignment: Alignment,
+    width: Option<usize>,
+    max_width: Option<usize>,
+    styles: Vec<Style>,
+}
+
+impl Placeholder {
+    /// Parses the contents of a placeholder in the form of `path?:spec|style|style`.
+    fn parse(template: &str, contents: &str) -> Result<Placeholder, TemplateParseError> {
+        let mut parts = contents.split('|');
+        let field = parts.next().unwrap_or_default();
+        let styles = parts
+            .map(|style| Style::parse(template, style))
+            .collect::<Result<Vec<Style>, TemplateParseError>>()?;
+
+        let (path, spec) = match field.split_once(':') {
+            Some((path, spec)) => (path.trim(), Some(spec)),
+            None => (field.trim(), None),
+        };
+        let (path, is_optional) = match path.strip_suffix('?') {
+            Some(path) => (path, true),
+            None => (path, false),
+        };
+
+        if path.is_empty() {
+            return Err(TemplateParseError::new(template, "field name is empty"));
+        }
+
+        let mut placeholder = Placeholder {
+            path: path.to_string(),
+            is_optional: is_optional || path == EXTRAS_PLACEHOLDER,
+            time_format: None,
+            alignment: Alignment::Left,
+            width: None,
+            max_width: None,
+            styles,
+        };
+
+        match spec {
+            Some(spec) if path == "time" => {
+                if StrftimeItems::new(spec).any(|item| item == Item::Error) {
+                    return Err(TemplateParseError::new(
+                        template,
+                        format!("invalid time format [{spec}]"),
+                    ));
+                }
+                placeholder.time_format = Some(spec.to_string());
+            }
+            Some(spec) => placeholder.parse_spec(template, spec)?,
+            None => {}
+        }
+
+        Ok(placeholder)
+    }
+
+    /// Parses a width specification in the form of `[<>^][width][.max_width]`.
+    fn parse_spec(&mut self, template: &str, spec: &str) -> Result<(), TemplateParseError> {
+        let invalid = || TemplateParseError::new(template, format!("invalid width [{spec}]"));
+        let parse_number = |number: &str| match number {
+            "" => Ok(None),
+            _ => number.parse::<usize>().map(Some).map_err(|_| invalid()),
+        };
+
+        let mut rest = spec;
+        self.alignment = match rest.chars().next() {
+            Some('<') => Alignment::Left,
+            Some('>') => Alignment::Right,
+            Some('^') => Alignment::Center,
+            _ => Alignment::Left,
+        };
+        if rest.starts_with(['<', '>', '^']) {
+            rest = &rest[1..];
+        }
+
+        let (width, max_width) = match rest.split_once('.') {
+            Some((width, max_width)) => (width, max_width),
+            None => (rest, ""),
+        };
+        self.width = parse_number(width)?;
+        self.max_width = parse_number(max_width)?;
+
+        Ok(())
+    }
+
+    /// Returns the text of the field in the record, or `None` if it is missing.
+    fn value(
+        &self,
+        log: &BunyanLine,
+        referenced: &[&str],
+        output_config: &LoggerOutputConfig,
+    ) -> Option<String> {
+        match self.path.as_str() {
+            "time" => {
+                let local_time = log.time.with_timezone(&Local);
+                Some(
+                    match (&self.time_format, output_config.display_local_time) {
+                        (Some(format), true) => local_time.format(format).to_string(),
+                        (Some(format), false) => log.time.format(format).to_string(),
+                        (None, true) => local_time.to_rfc3339_opts(SecondsFormat::Millis, true),
+                        (None, false) => log.time.to_rfc3339_opts(SecondsFormat::Millis, true),
+                    },
+                )
+            }
+            "level" => Some(LogLevel::from(log.level).as_string().into_owned()),
+            "name" => Some(log.name.clone()),
+            "hostname" => Some(log.hostname.clone()),
+            "pid" => Some(log.pid.to_string()),
+            "msg" => Some(log.msg.clone()),
+            "component" => log.component.clone(),
+            "v" => log.v.map(|v| v.to_string()),
+            EXTRAS_PLACEHOLDER => {
+                let extras: Vec<String> = log
+                    .other
+                    .iter()
+                    .filter(|(key, _)| !referenced.contains(&key.as_str()))
+                    .map(|(key, value)| format!("{}={}", key, extra_value(value)))
+                    .collect();
+                Some(extras.join(" ")).filter(|extras| !extras.is_empty())
+            }
+            path => lookup_path(&log.other, path).map(|value| string_or_value!(value)),
+        }
+    }
+
+    /// Returns the text truncated and padded to the configured widths.
+    fn fit(&self, text: String) -> String {
+        let text = match self.max_width {
+            Some(max_width) => text.chars().take(max_width).collect(),
+            None => text,
+        };
+
+        match self.width {
+            Some(width) => match self.alignment {
+                Alignment::Left => format!("{text:<width$}"),
+                Alignment::Right => format!("{text:>width$}"),
+                Alignment::Center => format!("{text:^width$}"),
+            },
+            None => text,
+        }
+    }
+}
+
+/// Returns the value of an extra field as written in `key=value` form. Strings are written
+/// as is unless they contain whitespace, in which case they are quoted.
+fn extra_value(value: &Value) -> String {
+    match value.as_str() {
+        Some(text) if !text.is_empty() && !text.contains(char::is_whitespace) => text.to_string(),
+        _ => value.to_string(),
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// A user defined single line layout of records such as
+/// `{time:%H:%M:%S} {level:>5} [{name}/{component}] {msg} {req_id?}`. Placeholders
+/// reference fields by dotted path and may specify an alignment and width (`{level:>5}`),
+/// a maximum width (`{msg:.80}`) and styles (`{msg|cyan|bold}`, `{level|level}`). The
+/// `time` field accepts a strftime format instead. A missing field is written as `-`
+/// unless it is marked as optional (`{req_id?}`), in which case it is omitted along with
+/// the space preceding it. The `{*}` placeholder is replaced by the remaining fields in
+/// `key=value` form. Literal braces are written as `{{` and `}}`.
+///
+#[derive(Debug, Clone)]
+pub struct Template {
+    source: String,
+    segments: Vec<Segment>,
+    /// Top-level fields referenced by placeholders, which are excluded from the extras
+    referenced: Vec<String>,
+}
+
+impl Template {
+    /// Parses a template.
+    ///
+    /// # Errors
+    ///
+    /// If a placeholder is not closed, names no field or has an invalid width or style, a
+    /// `TemplateParseError` is returned.
+    ///
+    pub fn parse<S: Into<String>>(source: S) -> Result<Template, TemplateParseError> {
+        let source = source.into();
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut contents = String::new();
+                    let mut is_closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            is_closed = true;
+                            break;
+                        }
+                        contents.push(c);
+                    }
+
+                    if !is_closed {
+                        return Err(TemplateParseError::new(
+                            source.as_str(),
+                            "placeholder is not closed",
+                        ));
+                    }
+
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(Placeholder::parse(
+                        &source, &contents,
+                    )?));
+                }
+                '}' => {
+                    return Err(TemplateParseError::new(
+                        source.as_str(),
+                        "unmatched } (write }} for a literal brace)",
+                    ))
+                }
+                _ => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        let referenced = segments
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Placeholder(placeholder) => placeholder.path.split('.').next(),
+                Segment::Literal(_) => None,
+            })
+            .chain(CORE_FIELDS)
+            .map(str::to_string)
+            .collect();
+
+        Ok(Template {
+            source,
+            segments,
+            referenced,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Writes the record as a single line laid out by the template.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Write implementation to output data to
+    /// * `log` - Log record to write
+    /// * `output_config` - Configuration containing the time zone to display
+    ///
+    pub fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        log: &BunyanLine,
+        output_config: &LoggerOutputConfig,
+    ) {
+        let level = LogLevel::from(log.level);
+        let referenced: Vec<&str> = self.referenced.iter().map(String::as_str).collect();
+        let mut line = String::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => line.push_str(text),
+                Segment::Placeholder(placeholder) => {
+                    let text = match placeholder.value(log, &referenced, output_config) {
+                        Some(text) => text,
+                        None if placeholder.is_optional => {
+                            if line.ends_with(' ') {
+                                line.pop();
+                            }
+                            continue;
+                        }
+                        None => MISSING_VALUE.to_string(),
+                    };
+
+                    let styled = placeholder
+                        .styles
+                        .iter()
+                        .fold(placeholder.fit(text).normal(), |text, style| {
+                            style.apply(text, &level)
+                        });
+                    line.push_str(&styled.to_string());
+                }
+            }
+        }
+
+        wln!(writer, "{}", line);
+    }
+}
+
+// Templates are compared by their source so that they can be used within `LogFormat`
+impl PartialEq for Template {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Template {}
+
+impl PartialOrd for Template {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Template {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.source.cmp(&other.source)
+    }
+}
+
+impl Hash for Template {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.source.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LogFormat;
+
+    fn render(template: &str, json: &str) -> String {
+        let template = Template::parse(template).unwrap();
+        let log: BunyanLine = serde_json::from_str(json).expect("invalid test JSON");
+        let output_config = LoggerOutputConfig {
+            format: LogFormat::Template(template.clone()),
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        template.write(&mut output, &log, &output_config);
+        String::from_utf8(output).unwrap()
+    }
+
+    const RECORD: &str = r#"{"name":"api","hostname":"h","pid":1,"level":30,"msg":"done",
+        "time":"2020-01-01T12:34:56.789Z","v":0,"req_id":"abc",
+        "req":{"method":"GET","url":"/users"},"latency":12}"#;
+
+    #[test]
+    fn can_render_fields_with_widths() {
+        assert_eq!(
+            render(
+                "{time:%H:%M:%S} {level:>5} [{name}/{component}] {msg:.3} {req.method:^5}|",
+                RECORD
+            ),
+            "12:34:56  INFO [api/-] don  GET |\n"
+        );
+    }
+
+    #[test]
+    fn optional_fields_are_omitted() {
+        assert_eq!(render("{msg} {trace_id?} {req_id?}", RECORD), "done abc\n");
+    }
+
+    #[test]
+    fn can_render_extra_fields() {
+        assert_eq!(
+            render("{{{msg}}} {req_id} {*}", RECORD),
+            "{done} abc req={\"method\":\"GET\",\"url\":\"/users\"} latency=12\n"
+        );
+    }
+
+    #[test]
+    fn invalid_templates_are_errors() {
+        assert!(Template::parse("{msg").is_err());
+        assert!(Template::parse("msg}").is_err());
+        assert!(Template::parse("{}").is_err());
+        assert!(Template::parse("{level:>x}").is_err());
+        assert!(Template::parse("{level|sparkly}").is_err());
+        assert!(Template::parse("{time:%Q}").is_err());
+        assert!(Template::parse(DEFAULT_TEMPLATE).is_ok());
+    }
+}
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+
+use crate::context_buffer::parse_duration;
+use crate::date_deserializer::parse_timestamp;
+use crate::errors::TimeRangeParseError;
+
+/// Range of times of the records to display. Either bound may be left open.
+#[derive(Debug, Clone, Default)]
+pub struct TimeRange {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl TimeRange {
+    /// Parses the bounds of a time range. Each bound is either a timestamp (eg
+    /// `2020-01-01T12:00:00Z`) or a duration (eg `15m`) that is subtracted from `now`.
+    ///
+    /// # Arguments
+    ///
+    /// * `since` - Optional earliest time of the records to display
+    /// * `until` - Optional latest time of the records to display
+    /// * `now` - Time that durations are relative to
+    ///
+    /// # Errors
+    ///
+    /// If a bound is neither a timestamp nor a duration, or the range ends before it
+    /// starts, a `TimeRangeParseError` is returned.
+    ///
+    pub fn parse(
+        since: Option<&str>,
+        until: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<TimeRange, TimeRangeParseError> {
+        let parse_bound = |input: &str| {
+            parse_timestamp(input.trim())
+                .ok()
+                .or_else(|| parse_duration(input).ok().map(|ago| now - ago))
+                .ok_or_else(|| {
+                    TimeRangeParseError::new(input, "expected a timestamp or a duration such as 1h")
+                })
+        };
+
+        let range = TimeRange {
+            since: since.map(parse_bound).transpose()?,
+            until: until.map(parse_bound).transpose()?,
+        };
+
+        if let (Some(since), Some(until)) = (range.since, range.until) {
+            if until < since {
+                return Err(TimeRangeParseError::new(
+                    until.to_rfc3339(),
+                    "end of range is before its start",
+                ));
+            }
+        }
+
+        Ok(range)
+    }
+
+    /// Returns true if the passed time is within the range.
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| time >= since) && self.until.is_none_or(|until| time <= until)
+    }
+
+    /// Returns true if any time between `start` and `end` is within the range.
+    pub fn overlaps(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| end >= since) && self.until.is_none_or(|until| start <= until)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        parse_timestamp(time).unwrap()
+    }
+
+    #[test]
+    fn can_parse_timestamps_and_durations() {
+        let now = at("2020-01-01T12:00:00Z");
+        let range = TimeRange::parse(Some("1h"), Some("2020-01-01T11:30:00Z"), now).unwrap();
+
+        assert_eq!(range.since, Some(at("2020-01-01T11:00:00Z")));
+        assert_eq!(range.until, Some(at("2020-01-01T11:30:00Z")));
+        assert!(TimeRange::parse(Some("yesterday"), None, now).is_err());
+        assert!(TimeRange::parse(Some("1h"), Some("2h"), now).is_err());
+    }
+
+    #[test]
+    fn can_match_times_within_range() {
+        let range = TimeRange {
+            since: Some(at("2020-01-01T11:00:00Z")),
+            until: None,
+        };
+
+        assert!(range.contains(at("2020-01-01T11:00:00Z")));
+        assert!(!range.contains(at("2020-01-01T10:59:59Z")));
+        assert!(range.overlaps(at("2020-01-01T10:00:00Z"), at("2020-01-01T11:00:00Z")));
+        assert!(!range.overlaps(at("2020-01-01T09:00:00Z"), at("2020-01-01T10:00:00Z")));
+    }
+}
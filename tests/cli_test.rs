@@ -0,0 +1,117 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output};
+
+// These tests run the bunyan binary to cover behavior decided by its command line handling
+
+fn bunyan(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_bunyan"))
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Couldn't run bunyan")
+}
+
+// HEAD AND TAIL
+
+#[test]
+fn tail_cannot_be_combined_with_sampling_or_rate_limiting() {
+    for throttle in [["--sample", "1/3"], ["--rate-limit", "5/s"]] {
+        let output = bunyan(&[
+            "--tail",
+            "5",
+            throttle[0],
+            throttle[1],
+            "tests/corpus/simple.log",
+        ]);
+
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("cannot be used with"), "{stderr}");
+    }
+
+    let output = bunyan(&["--head", "5", "--sample", "1/3", "tests/corpus/simple.log"]);
+    assert!(output.status.success());
+}
+
+// STATE
+
+/// Returns the messages of the records written by `-o short`, which end each line.
+fn messages(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.rsplit(' ').next())
+        .map(String::from)
+        .collect()
+}
+
+fn append_record(path: &Path, msg: &str) {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .unwrap();
+    writeln!(
+        file,
+        r#"{{"v":0,"level":30,"name":"app","hostname":"host","pid":1,"time":"2020-01-01T00:00:00Z","msg":"{msg}"}}"#
+    )
+    .unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn since_last_run_with_rotated_copies_only_shows_new_records() {
+    let dir = std::env::temp_dir().join(format!("bunyan-cli-rotated-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let log = dir.join("app.log");
+    let state = dir.join("state");
+    let args = [
+        "-o",
+        "short",
+        "--rotated",
+        "--since-last-run",
+        "--state",
+        state.to_str().unwrap(),
+        log.to_str().unwrap(),
+    ];
+
+    append_record(&log, "one");
+    append_record(&log, "two");
+    assert_eq!(messages(&bunyan(&args)), vec!["one", "two"]);
+
+    append_record(&log, "three");
+    fs::rename(&log, dir.join("app.log.1")).unwrap();
+    append_record(&log, "four");
+    assert_eq!(messages(&bunyan(&args)), vec!["three", "four"]);
+    assert!(messages(&bunyan(&args)).is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// INDEX
+
+#[test]
+fn index_is_only_created_when_requested() {
+    let dir = std::env::temp_dir().join(format!("bunyan-cli-index-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let log = dir.join("app.log");
+    let index = dir.join("app.log.bvidx");
+    append_record(&log, "one");
+
+    assert!(bunyan(&["-l", "error", log.to_str().unwrap()])
+        .status
+        .success());
+    assert!(!index.exists());
+
+    assert!(bunyan(&["--index", "-l", "error", log.to_str().unwrap()])
+        .status
+        .success());
+    assert!(index.exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -7,10 +7,28 @@ use std::fs::File;
 use std::io::BufReader;
 use std::io::Read;
 
-use bunyan_view::{LogFormat, LoggerOutputConfig};
+use bunyan_view::{
+    CollapseConfig, ContextConfig, FieldFilter, FieldProjection, LevelFilter, LogFormat,
+    LoggerOutputConfig, RequestTracer, Template, TimeRange,
+};
 use bytes::BufMut;
 
+fn output_config(format: LogFormat) -> LoggerOutputConfig {
+    LoggerOutputConfig {
+        format,
+        ..Default::default()
+    }
+}
+
 fn assert_equals_to_file(filename: &str, expected_filename: &str, format: LogFormat) {
+    assert_equals_to_file_with_config(filename, expected_filename, &output_config(format));
+}
+
+fn assert_equals_to_file_with_config(
+    filename: &str,
+    expected_filename: &str,
+    output_config: &LoggerOutputConfig,
+) {
     let mut writer = vec![].writer();
     let file = File::open(filename).expect("File not found");
     let reader = BufReader::new(file);
@@ -24,17 +42,7 @@ fn assert_equals_to_file(filename: &str, expected_filename: &str, format: LogFor
     .concat();
     expected_file.read_to_string(&mut expected).expect(msg);
 
-    let output_config = LoggerOutputConfig {
-        indent: 4,
-        is_debug: false,
-        is_strict: false,
-        level: None,
-        condition_filter: None,
-        display_local_time: false,
-        format,
-    };
-
-    bunyan_view::write_bunyan_output(&mut writer, reader, &output_config);
+    bunyan_view::write_bunyan_output(&mut writer, reader, output_config);
     let actual_bytes: Vec<u8> = writer.into_inner();
     let actual = std::str::from_utf8(&actual_bytes).expect("Couldn't convert bytes");
 
@@ -960,3 +968,623 @@ fn inspect_with_weird_extra_params() {
         LogFormat::Inspect,
     );
 }
+
+// FILTERED OUTPUT
+
+fn level_filtered_config(format: LogFormat) -> LoggerOutputConfig {
+    LoggerOutputConfig {
+        level: Some(LevelFilter::parse("error").expect("invalid level")),
+        ..output_config(format)
+    }
+}
+
+#[test]
+fn bunyan_format_level_filtered_all() {
+    assert_equals_to_file_with_config(
+        "tests/corpus/all.log",
+        "tests/expectations/filtered/all.log.bunyan.expected",
+        &level_filtered_config(LogFormat::Json(0)),
+    );
+}
+
+#[test]
+fn inspect_level_filtered_all() {
+    assert_equals_to_file_with_config(
+        "tests/corpus/all.log",
+        "tests/expectations/filtered/all.log.inspect.expected",
+        &level_filtered_config(LogFormat::Inspect),
+    );
+}
+
+// REQUEST TRACING
+
+#[test]
+fn long_format_trace_all_requests() {
+    let file = File::open("tests/corpus/trace.log").expect("File not found");
+    let mut expected = String::new();
+    File::open("tests/expectations/trace/trace.log.expected")
+        .expect("file not found")
+        .read_to_string(&mut expected)
+        .expect("There was a problem opening the expectation file");
+
+    let output_config = output_config(LogFormat::Long);
+    let mut tracer = RequestTracer::new(None, vec!["trace_id"]);
+    bunyan_view::collect_trace_records(BufReader::new(file), &output_config, &mut tracer);
+
+    let mut writer = vec![].writer();
+    bunyan_view::write_trace_output(&mut writer, tracer, &output_config);
+    let actual_bytes: Vec<u8> = writer.into_inner();
+    let actual = std::str::from_utf8(&actual_bytes).expect("Couldn't convert bytes");
+
+    assert_eq!(actual, expected);
+}
+
+// HEAD AND TAIL
+
+#[test]
+fn long_format_head_all() {
+    let output_config = LoggerOutputConfig {
+        head: Some(3),
+        ..output_config(LogFormat::Long)
+    };
+    assert_equals_to_file_with_config(
+        "tests/corpus/all.log",
+        "tests/expectations/filtered/all.log.head.expected",
+        &output_config,
+    );
+}
+
+#[test]
+fn long_format_level_filtered_tail_all() {
+    let output_config = LoggerOutputConfig {
+        level: Some(LevelFilter::parse("warn").expect("invalid level")),
+        tail: Some(2),
+        ..output_config(LogFormat::Long)
+    };
+    assert_equals_to_file_with_config(
+        "tests/corpus/all.log",
+        "tests/expectations/filtered/all.log.tail.expected",
+        &output_config,
+    );
+}
+
+#[test]
+fn long_format_head_with_context_all() {
+    let output_config = LoggerOutputConfig {
+        level: Some(LevelFilter::parse("error").expect("invalid level")),
+        context: Some(ContextConfig {
+            before: 0,
+            after: 1,
+            time_window: None,
+        }),
+        head: Some(1),
+        ..output_config(LogFormat::Long)
+    };
+    assert_equals_to_file_with_config(
+        "tests/corpus/all.log",
+        "tests/expectations/filtered/all.log.head-context.expected",
+        &output_config,
+    );
+}
+
+#[test]
+fn long_format_tail_with_context_all() {
+    let output_config = LoggerOutputConfig {
+        level: Some(LevelFilter::parse("error").expect("invalid level")),
+        context: Some(ContextConfig {
+            before: 2,
+            after: 1,
+            time_window: None,
+        }),
+        tail: Some(1),
+        ..output_config(LogFormat::Long)
+    };
+    assert_equals_to_file_with_config(
+        "tests/corpus/all.log",
+        "tests/expectations/filtered/all.log.tail-context.expected",
+        &output_config,
+    );
+}
+
+#[test]
+fn long_format_level_filtered_tail_output_all() {
+    let output_config = LoggerOutputConfig {
+        level: Some(LevelFilter::parse("warn").expect("invalid level")),
+        tail: Some(2),
+        ..output_config(LogFormat::Long)
+    };
+    let file = File::open("tests/corpus/all.log").expect("File not found");
+    let mut expected = String::new();
+    File::open("tests/expectations/filtered/all.log.tail.expected")
+        .expect("file not found")
+        .read_to_string(&mut expected)
+        .expect("There was a problem opening the expectation file");
+
+    let mut writer = vec![].writer();
+    bunyan_view::write_tail_output(&mut writer, BufReader::new(file), &output_config, 2)
+        .expect("Unable to read file");
+    let actual_bytes: Vec<u8> = writer.into_inner();
+    let actual = std::str::from_utf8(&actual_bytes).expect("Couldn't convert bytes");
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn tail_offset_starts_at_last_matching_records() {
+    let output_config = LoggerOutputConfig {
+        level: Some(LevelFilter::parse("warn").expect("invalid level")),
+        ..output_config(LogFormat::Long)
+    };
+    let mut file = File::open("tests/corpus/all.log").expect("File not found");
+    let offset =
+        bunyan_view::find_tail_offset(&mut file, &output_config, 2).expect("Unable to read file");
+
+    let mut contents = String::new();
+    File::open("tests/corpus/all.log")
+        .expect("File not found")
+        .read_to_string(&mut contents)
+        .expect("Unable to read file");
+    let expected_line = contents
+        .lines()
+        .find(|line| line.contains("\"level\":55"))
+        .expect("Missing level 55 record");
+
+    assert!(contents[offset as usize..].starts_with(expected_line));
+}
+
+// INDEXED OUTPUT
+
+#[test]
+fn indexed_output_matches_unindexed_output() {
+    let dir = std::env::temp_dir().join(format!("bunyan-corpus-index-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("Unable to create directory");
+    let path = dir.join("all.log");
+    std::fs::copy("tests/corpus/all.log", &path).expect("Unable to copy corpus");
+
+    let output_config = LoggerOutputConfig {
+        level: Some(LevelFilter::parse("warn").expect("invalid level")),
+        time_range: Some(
+            TimeRange::parse(Some("2012-02-08T22:56:52Z"), None, chrono::Utc::now())
+                .expect("invalid time"),
+        ),
+        use_index: true,
+        ..output_config(LogFormat::Long)
+    };
+
+    let mut expected = vec![].writer();
+    let file = File::open(&path).expect("File not found");
+    bunyan_view::write_bunyan_output(&mut expected, BufReader::new(file), &output_config);
+
+    // The second run reads the index created by the first
+    for _ in 0..2 {
+        let mut actual = vec![].writer();
+        bunyan_view::write_bunyan_file(&mut actual, &path, &output_config)
+            .expect("Unable to read file");
+        assert_eq!(
+            std::str::from_utf8(expected.get_ref()).expect("Couldn't convert bytes"),
+            std::str::from_utf8(actual.get_ref()).expect("Couldn't convert bytes")
+        );
+    }
+    assert!(bunyan_view::LogIndex::index_path(&path).exists());
+
+    std::fs::remove_dir_all(dir).expect("Unable to remove directory");
+}
+
+#[test]
+fn indexed_output_skips_blocks_outside_time_range() {
+    let dir = std::env::temp_dir().join(format!("bunyan-corpus-blocks-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("Unable to create directory");
+    let path = dir.join("app.log");
+    let contents: String = (0..3000)
+        .map(|i| {
+            format!(
+                "{{\"name\":\"app\",\"pid\":1,\"hostname\":\"h\",\"level\":{},\"msg\":\"record {}\",\"time\":\"2020-01-01T{:02}:{:02}:{:02}Z\",\"v\":0}}\n",
+                if i % 7 == 0 { 50 } else { 30 },
+                i,
+                i / 3600,
+                i / 60 % 60,
+                i % 60
+            )
+        })
+        .collect();
+    std::fs::write(&path, contents).expect("Unable to write log");
+
+    let output_config = LoggerOutputConfig {
+        time_range: Some(
+            TimeRange::parse(
+                Some("2020-01-01T00:20:00Z"),
+                Some("2020-01-01T00:40:00Z"),
+                chrono::Utc::now(),
+            )
+            .expect("invalid time"),
+        ),
+        level: Some(LevelFilter::parse("error").expect("invalid level")),
+        use_index: true,
+        ..output_config(LogFormat::Long)
+    };
+
+    let mut expected = vec![].writer();
+    let file = File::open(&path).expect("File not found");
+    bunyan_view::write_bunyan_output(&mut expected, BufReader::new(file), &output_config);
+
+    let mut actual = vec![].writer();
+    bunyan_view::write_bunyan_file(&mut actual, &path, &output_config)
+        .expect("Unable to read file");
+    assert!(!expected.get_ref().is_empty());
+    assert_eq!(
+        std::str::from_utf8(expected.get_ref()).expect("Couldn't convert bytes"),
+        std::str::from_utf8(actual.get_ref()).expect("Couldn't convert bytes")
+    );
+
+    std::fs::remove_dir_all(dir).expect("Unable to remove directory");
+}
+
+#[test]
+fn indexed_output_skips_blocks_by_level_and_name_alone() {
+    let dir = std::env::temp_dir().join(format!("bunyan-corpus-names-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("Unable to create directory");
+    let path = dir.join("app.log");
+    let contents: String = (0..3000)
+        .map(|i| {
+            format!(
+                "{{\"name\":\"{}\",\"pid\":1,\"hostname\":\"h\",\"level\":{},\"msg\":\"record {}\",\"time\":\"2020-01-01T00:00:00Z\",\"v\":0}}\n",
+                if i / 1000 == 1 { "db" } else { "app" },
+                if i % 7 == 0 { 50 } else { 30 },
+                i
+            )
+        })
+        .collect();
+    std::fs::write(&path, contents).expect("Unable to write log");
+
+    let output_config = LoggerOutputConfig {
+        level: Some(LevelFilter::parse("error").expect("invalid level")),
+        field_filter: Some(FieldFilter::new(["name=db"]).expect("invalid field filter")),
+        use_index: true,
+        ..output_config(LogFormat::Long)
+    };
+
+    let mut expected = vec![].writer();
+    let file = File::open(&path).expect("File not found");
+    bunyan_view::write_bunyan_output(&mut expected, BufReader::new(file), &output_config);
+
+    let mut actual = vec![].writer();
+    bunyan_view::write_bunyan_file(&mut actual, &path, &output_config)
+        .expect("Unable to read file");
+    assert!(!expected.get_ref().is_empty());
+    assert_eq!(
+        std::str::from_utf8(expected.get_ref()).expect("Couldn't convert bytes"),
+        std::str::from_utf8(actual.get_ref()).expect("Couldn't convert bytes")
+    );
+    assert!(bunyan_view::LogIndex::index_path(&path).exists());
+
+    std::fs::remove_dir_all(dir).expect("Unable to remove directory");
+}
+
+// TEMPLATE OUTPUT
+
+#[test]
+fn template_format_withreq() {
+    let template = Template::parse(
+        "{time:%H:%M:%S} {level:>5} [{name}/{component?}] {msg:.40} {req.method?} {res.statusCode?} {*}",
+    )
+    .expect("invalid template");
+    assert_equals_to_file(
+        "tests/corpus/withreq.log",
+        "tests/expectations/template/withreq.log.expected",
+        LogFormat::Template(template),
+    );
+}
+
+// LOGFMT OUTPUT
+
+#[test]
+fn logfmt_format_withreq() {
+    assert_equals_to_file(
+        "tests/corpus/withreq.log",
+        "tests/expectations/logfmt/withreq.log.expected",
+        LogFormat::Logfmt,
+    );
+}
+
+#[test]
+fn logfmt_format_error_with_stack() {
+    assert_equals_to_file(
+        "tests/corpus/error-with-stack.log",
+        "tests/expectations/logfmt/error-with-stack.log.expected",
+        LogFormat::Logfmt,
+    );
+}
+
+// CSV AND TSV OUTPUT
+
+fn assert_delimited_equals_to_file(filename: &str, expected_filename: &str, format: LogFormat) {
+    let output_config = output_config(format);
+    let mut writer = vec![].writer();
+    let file = File::open(filename).expect("File not found");
+
+    bunyan_view::write_output_header(&mut writer, &output_config);
+    bunyan_view::write_bunyan_output(&mut writer, BufReader::new(file), &output_config);
+
+    let mut expected = String::new();
+    File::open(expected_filename)
+        .expect("file not found")
+        .read_to_string(&mut expected)
+        .expect("Unable to read expectation file");
+
+    assert_eq!(
+        expected,
+        std::str::from_utf8(writer.get_ref()).expect("Couldn't convert bytes")
+    );
+}
+
+fn columns(columns: &str) -> Vec<String> {
+    columns.split(',').map(str::to_string).collect()
+}
+
+#[test]
+fn csv_format_withreq() {
+    assert_delimited_equals_to_file(
+        "tests/corpus/withreq.log",
+        "tests/expectations/delimited/withreq.log.csv.expected",
+        LogFormat::Csv(columns(
+            "time,level,name,req_id,req.url,res.statusCode,latency,req.headers",
+        )),
+    );
+}
+
+#[test]
+fn tsv_format_error_with_stack() {
+    assert_delimited_equals_to_file(
+        "tests/corpus/error-with-stack.log",
+        "tests/expectations/delimited/error-with-stack.log.tsv.expected",
+        LogFormat::Tsv(columns("time,level,msg,err.stack")),
+    );
+}
+
+// HTML OUTPUT
+
+#[test]
+fn html_format_withreq() {
+    let output_config = output_config(LogFormat::Html);
+    let mut writer = vec![].writer();
+    let file = File::open("tests/corpus/withreq.log").expect("File not found");
+
+    bunyan_view::write_output_header(&mut writer, &output_config);
+    bunyan_view::write_bunyan_output(&mut writer, BufReader::new(file), &output_config);
+    bunyan_view::write_output_footer(&mut writer, &output_config);
+
+    let mut expected = String::new();
+    File::open("tests/expectations/html/withreq.log.expected")
+        .expect("file not found")
+        .read_to_string(&mut expected)
+        .expect("Unable to read expectation file");
+
+    assert_eq!(
+        expected,
+        std::str::from_utf8(writer.get_ref()).expect("Couldn't convert bytes")
+    );
+}
+
+// MARKDOWN OUTPUT
+
+#[test]
+fn markdown_format_withreq() {
+    assert_equals_to_file(
+        "tests/corpus/withreq.log",
+        "tests/expectations/markdown/withreq.log.expected",
+        LogFormat::Markdown,
+    );
+}
+
+#[test]
+fn markdown_format_error_with_stack() {
+    assert_equals_to_file(
+        "tests/corpus/error-with-stack.log",
+        "tests/expectations/markdown/error-with-stack.log.expected",
+        LogFormat::Markdown,
+    );
+}
+
+#[test]
+fn markdown_format_req_with_newlines() {
+    assert_equals_to_file(
+        "tests/corpus/req-with-newlines.log",
+        "tests/expectations/markdown/req-with-newlines.log.expected",
+        LogFormat::Markdown,
+    );
+}
+
+// FIELD PROJECTION
+
+#[test]
+fn bunyan_format_withreq_fields() {
+    let output_config = LoggerOutputConfig {
+        projection: Some(
+            FieldProjection::new(Some("req_id,req.url,res.statusCode"), None)
+                .expect("invalid fields"),
+        ),
+        ..output_config(LogFormat::Json(0))
+    };
+    assert_equals_to_file_with_config(
+        "tests/corpus/withreq.log",
+        "tests/expectations/projection/withreq-fields.log.expected",
+        &output_config,
+    );
+}
+
+#[test]
+fn bunyan_format_withreq_exclude_fields() {
+    let output_config = LoggerOutputConfig {
+        projection: Some(
+            FieldProjection::new(None, Some("req.headers,res.headers,route"))
+                .expect("invalid fields"),
+        ),
+        ..output_config(LogFormat::Json(0))
+    };
+    assert_equals_to_file_with_config(
+        "tests/corpus/withreq.log",
+        "tests/expectations/projection/withreq-exclude-fields.log.expected",
+        &output_config,
+    );
+}
+
+// OTLP JSON OUTPUT
+
+#[test]
+fn otlp_json_format_withreq() {
+    assert_equals_to_file(
+        "tests/corpus/withreq.log",
+        "tests/expectations/otlp/withreq.log.expected",
+        LogFormat::OtlpJson,
+    );
+}
+
+#[test]
+fn otlp_json_format_otel() {
+    assert_equals_to_file(
+        "tests/corpus/otel.log",
+        "tests/expectations/otlp/otel.log.expected",
+        LogFormat::OtlpJson,
+    );
+}
+
+#[test]
+fn otlp_json_format_all() {
+    // Lines that aren't records are written to stderr rather than into the NDJSON output
+    assert_equals_to_file(
+        "tests/corpus/all.log",
+        "tests/expectations/otlp/all.log.expected",
+        LogFormat::OtlpJson,
+    );
+}
+
+// GELF AND SYSLOG OUTPUT
+
+#[test]
+fn gelf_format_withreq() {
+    assert_equals_to_file(
+        "tests/corpus/withreq.log",
+        "tests/expectations/gelf/withreq.log.expected",
+        LogFormat::Gelf,
+    );
+}
+
+#[test]
+fn gelf_format_otel() {
+    assert_equals_to_file(
+        "tests/corpus/otel.log",
+        "tests/expectations/gelf/otel.log.expected",
+        LogFormat::Gelf,
+    );
+}
+
+#[test]
+fn syslog_format_withreq() {
+    assert_equals_to_file(
+        "tests/corpus/withreq.log",
+        "tests/expectations/syslog/withreq.log.expected",
+        LogFormat::Syslog,
+    );
+}
+
+#[test]
+fn syslog_format_otel() {
+    assert_equals_to_file(
+        "tests/corpus/otel.log",
+        "tests/expectations/syslog/otel.log.expected",
+        LogFormat::Syslog,
+    );
+}
+
+#[test]
+fn syslog_format_level_filtered_all() {
+    let output_config = LoggerOutputConfig {
+        is_strict: true,
+        level: Some(LevelFilter::parse("warn").expect("invalid level")),
+        ..output_config(LogFormat::Syslog)
+    };
+    assert_equals_to_file_with_config(
+        "tests/corpus/all.log",
+        "tests/expectations/syslog/all.log.warn.expected",
+        &output_config,
+    );
+}
+
+#[test]
+fn syslog_format_all() {
+    // Lines that aren't records are written to stderr rather than among the syslog messages
+    assert_equals_to_file(
+        "tests/corpus/all.log",
+        "tests/expectations/syslog/all.log.expected",
+        LogFormat::Syslog,
+    );
+}
+
+// ECS OUTPUT
+
+#[test]
+fn ecs_format_withreq() {
+    assert_equals_to_file(
+        "tests/corpus/withreq.log",
+        "tests/expectations/ecs/withreq.log.expected",
+        LogFormat::Ecs(None),
+    );
+}
+
+#[test]
+fn ecs_format_otel_bulk() {
+    assert_equals_to_file(
+        "tests/corpus/otel.log",
+        "tests/expectations/ecs/otel.log.bulk.expected",
+        LogFormat::Ecs(Some("logs-bunyan".to_string())),
+    );
+}
+
+#[test]
+fn ecs_format_all_bulk() {
+    // Lines that aren't complete records are written to stderr, so every action line is
+    // followed by the document it creates
+    assert_equals_to_file(
+        "tests/corpus/all.log",
+        "tests/expectations/ecs/all.log.bulk.expected",
+        LogFormat::Ecs(Some("logs-bunyan".to_string())),
+    );
+}
+
+// COMBINED ACCESS LOG OUTPUT
+
+#[test]
+fn combined_format_access() {
+    assert_equals_to_file(
+        "tests/corpus/access.log",
+        "tests/expectations/combined/access.log.expected",
+        LogFormat::Combined,
+    );
+}
+
+#[test]
+fn combined_format_withreq() {
+    assert_equals_to_file(
+        "tests/corpus/withreq.log",
+        "tests/expectations/combined/withreq.log.expected",
+        LogFormat::Combined,
+    );
+}
+
+#[test]
+fn combined_format_all_has_only_access_lines() {
+    // Raw lines and the summaries of collapsed records are written to stderr, and records
+    // that aren't requests are skipped, so nothing is left in the access log
+    let output_config = LoggerOutputConfig {
+        collapse: Some(CollapseConfig { window: None }),
+        ..output_config(LogFormat::Combined)
+    };
+    let mut writer = vec![].writer();
+    let file = File::open("tests/corpus/all.log").expect("File not found");
+    bunyan_view::write_bunyan_output(&mut writer, BufReader::new(file), &output_config);
+
+    assert_eq!(
+        "",
+        std::str::from_utf8(writer.get_ref()).expect("Couldn't convert bytes")
+    );
+}
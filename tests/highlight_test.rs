@@ -0,0 +1,118 @@
+extern crate bunyan_view;
+extern crate bytes;
+extern crate colored;
+#[macro_use]
+extern crate pretty_assertions;
+
+#[cfg(not(feature = "dumb_terminal"))]
+use std::fs::{self, File};
+#[cfg(not(feature = "dumb_terminal"))]
+use std::io::BufReader;
+
+#[cfg(not(feature = "dumb_terminal"))]
+use bunyan_view::LogFormat;
+use bunyan_view::{GrepFilter, LoggerOutputConfig};
+use bytes::BufMut;
+use colored::Colorize;
+
+// Colors are enabled for the whole process, so these tests are kept apart from the corpus
+// tests, which expect uncolored output
+
+#[cfg(not(feature = "dumb_terminal"))]
+fn assert_highlighted(filename: &str, expected_filename: &str, format: LogFormat) {
+    colored::control::set_override(true);
+
+    let output_config = LoggerOutputConfig {
+        grep_filter: Some(
+            GrepFilter::new("amon|agent|10\\.2\\.207|^9724a190", true, false, false).unwrap(),
+        ),
+        format,
+        ..Default::default()
+    };
+
+    let mut writer = vec![].writer();
+    let file = File::open(filename).expect("File not found");
+    bunyan_view::write_bunyan_output(&mut writer, BufReader::new(file), &output_config);
+    let actual_bytes: Vec<u8> = writer.into_inner();
+    let actual = std::str::from_utf8(&actual_bytes).expect("Couldn't convert bytes");
+
+    let expected = fs::read_to_string(expected_filename).expect("file not found");
+    assert_eq!(actual, expected);
+}
+
+// HIGHLIGHTED OUTPUT
+
+#[cfg(not(feature = "dumb_terminal"))]
+#[test]
+fn long_grep_all_highlights_every_searched_field() {
+    assert_highlighted(
+        "tests/corpus/withreq.log",
+        "tests/expectations/highlight/withreq.log.long.expected",
+        LogFormat::Long,
+    );
+}
+
+#[cfg(not(feature = "dumb_terminal"))]
+#[test]
+fn short_grep_all_highlights_every_searched_field() {
+    assert_highlighted(
+        "tests/corpus/withreq.log",
+        "tests/expectations/highlight/withreq.log.short.expected",
+        LogFormat::Short,
+    );
+}
+
+fn grep_all_output(input: &str, pattern: &str) -> String {
+    colored::control::set_override(true);
+
+    let output_config = LoggerOutputConfig {
+        grep_filter: Some(GrepFilter::new(pattern, true, false, false).unwrap()),
+        ..Default::default()
+    };
+
+    let mut writer = vec![].writer();
+    bunyan_view::write_bunyan_output(&mut writer, input.as_bytes(), &output_config);
+    String::from_utf8(writer.into_inner()).expect("Couldn't convert bytes")
+}
+
+/// Removes the ANSI escape sequences used for colors from the passed text.
+fn strip_colors(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            chars.by_ref().find(|c| *c == 'm');
+        } else {
+            stripped.push(c);
+        }
+    }
+
+    stripped
+}
+
+// HIGHLIGHTED VALUES
+
+#[test]
+fn highlighting_only_marks_matching_string_values() {
+    let input = r#"{"name":"app","hostname":"host","pid":1,"level":30,"msg":"hello","time":"2020-01-01T00:00:00.000Z","v":0,"payload":{"zzkey":1,"note":"a \"zz\" b"},"label":"x zz y"}"#;
+    let actual = grep_all_output(input, "zz");
+
+    assert_eq!(
+        strip_colors(&actual),
+        concat!(
+            "[2020-01-01T00:00:00.000Z]  INFO: app/1 on host: hello (label=\"x zz y\")\n",
+            "    payload: {\n",
+            "      \"zzkey\": 1,\n",
+            "      \"note\": \"a \\\"zz\\\" b\"\n",
+            "    }\n",
+        )
+    );
+
+    if cfg!(not(feature = "dumb_terminal")) {
+        let highlighted = "zz".black().on_yellow().to_string();
+        assert!(actual.contains("\"zzkey\""));
+        assert!(actual.contains(&format!("label=\"x {highlighted} y\"")));
+        assert!(actual.contains(&format!("\"a \\\"{highlighted}\\\" b\"")));
+    }
+}